@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+
+/// Measures the time spent playing a game. The clock can be stopped and started again, and can
+/// start from a previously elapsed duration, such that the time of a saved game carries over.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Clock {
+    /// The time elapsed before the clock was last started.
+    elapsed: Duration,
+    /// The moment the clock was last started, if it is running.
+    started: Option<Instant>,
+}
+
+impl Clock {
+    /// Returns a stopped clock, which has already counted the `elapsed` duration.
+    pub fn with_elapsed(elapsed: Duration) -> Self {
+        Self {
+            elapsed,
+            started: None,
+        }
+    }
+
+    /// Starts the clock, if it is not running yet.
+    pub fn start(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now())
+        }
+    }
+
+    /// Stops the clock, keeping the elapsed time.
+    pub fn stop(&mut self) {
+        self.elapsed = self.elapsed();
+        self.started = None
+    }
+
+    /// Returns the total elapsed time.
+    pub fn elapsed(&self) -> Duration {
+        match self.started {
+            Some(started) => self.elapsed + started.elapsed(),
+            None => self.elapsed,
+        }
+    }
+}
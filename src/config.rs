@@ -0,0 +1,302 @@
+use mine::field::{cell_count, NoGuess, SafeStart};
+use mine::{Difficulty, Field, Mines};
+use std::io;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// The size of a custom field, for the settings that are not given.
+const DEFAULT_WIDTH: usize = 9;
+const DEFAULT_HEIGHT: usize = 9;
+const DEFAULT_MINES: Mines = Mines::Count(10);
+
+/// The settings of a new field, which cannot be given for a loaded or resumed game, as it already
+/// has them.
+const NEW_FIELD_SETTINGS: &[&str] = &[
+    "difficulty",
+    "width",
+    "height",
+    "mines",
+    "safe-start",
+    "seed",
+    "no-guess",
+];
+
+// The settings of a game, shared by the terminal and graphical interface. This is not a doc
+// comment, because structopt would use it as the description of the subcommands.
+#[derive(StructOpt)]
+pub struct GameConfig {
+    /// Difficulty preset: beginner (9x9, 10 mines), intermediate (16x16, 40 mines), expert (30x16,
+    /// 99 mines) or custom. Defaults to custom if a width, height or number of mines is given,
+    /// and to beginner otherwise.
+    #[structopt(short, long)]
+    difficulty: Option<Difficulty>,
+
+    /// Field width, for a custom difficulty [default: 9]
+    #[structopt(short, long)]
+    width: Option<usize>,
+
+    /// Field height, for a custom difficulty [default: 9]
+    #[structopt(short, long)]
+    height: Option<usize>,
+
+    /// The number of mines to be placed in the field, or a percentage of its cells like 15%, for
+    /// a custom difficulty [default: 10]
+    #[structopt(short, long)]
+    mines: Option<Mines>,
+
+    /// Which cells are kept free of mines on the first reveal: off, cell or opening.
+    #[structopt(short, long, default_value = "opening")]
+    safe_start: SafeStart,
+
+    /// Seed for the mine layout. The same seed always results in the same field. A random seed
+    /// is used if none is given.
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Generate a field that can be solved from the first reveal without guessing. Falls back to
+    /// a random field if none is found in time. Needs the safe start of an opening.
+    #[structopt(long)]
+    no_guess: bool,
+
+    /// Practice mode: allows undoing the reveal that lost the game.
+    #[structopt(short, long)]
+    practice: bool,
+
+    /// Load a saved game from a file, instead of starting a new one.
+    #[structopt(long, parse(from_os_str), conflicts_with = "resume")]
+    #[structopt(conflicts_with_all = NEW_FIELD_SETTINGS)]
+    load: Option<PathBuf>,
+
+    /// Continue the game that was saved when quitting.
+    #[structopt(long, conflicts_with_all = NEW_FIELD_SETTINGS)]
+    resume: bool,
+
+    /// Record the game to a replay file, which can be played back with `mine replay`.
+    #[structopt(long, parse(from_os_str), conflicts_with_all = &["load", "resume"])]
+    pub record: Option<PathBuf>,
+}
+
+impl GameConfig {
+    /// Returns the width, height and number of mines of a new field, from the difficulty preset or
+    /// the custom settings.
+    fn size(&self) -> io::Result<(usize, usize, usize)> {
+        let custom = self.width.is_some() || self.height.is_some() || self.mines.is_some();
+        let difficulty = match self.difficulty {
+            Some(difficulty) => difficulty,
+            None if custom => Difficulty::Custom,
+            None => Difficulty::Beginner,
+        };
+
+        match difficulty.size() {
+            Some(_) if custom => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "--width, --height and --mines can only be used with --difficulty custom, not {}",
+                    difficulty
+                ),
+            )),
+            Some(size) => Ok(size),
+            None => {
+                let width = self.width.unwrap_or(DEFAULT_WIDTH);
+                let height = self.height.unwrap_or(DEFAULT_HEIGHT);
+                let cells = cell_count(width, height)?;
+                let mines = self.mines.unwrap_or(DEFAULT_MINES).count(cells);
+                Ok((width, height, mines))
+            }
+        }
+    }
+
+    /// Returns the field to play: the resumed game, the loaded game, or a new one.
+    pub fn field(&self) -> io::Result<Field> {
+        let mut f = if self.resume {
+            Field::resume()?
+        } else if let Some(path) = &self.load {
+            Field::load_from(path)?
+        } else {
+            // The solver can only start from a single revealed number by guessing.
+            if self.no_guess && self.safe_start != SafeStart::Opening {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "--no-guess needs the first reveal to open an area, so it cannot be used with --safe-start {}",
+                        self.safe_start
+                    ),
+                ));
+            }
+
+            let (width, height, mines) = self.size()?;
+            let mut f = match self.seed {
+                Some(seed) => Field::with_seed(height, width, mines, self.safe_start, seed)?,
+                None => Field::new(height, width, mines, self.safe_start)?,
+            };
+            f.set_no_guess(self.no_guess);
+            f
+        };
+        f.set_practice(self.practice || f.is_practice());
+
+        Ok(f)
+    }
+
+    /// Returns the file the save key writes the game to: the file it was loaded from, or a new
+    /// file named after its seed.
+    pub fn save_path(&self, f: &Field) -> PathBuf {
+        self.load
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("mine-{}.save", f.seed())))
+    }
+}
+
+/// Returns a new game with the settings of the field, and a new random seed.
+pub fn new_game(f: &Field) -> io::Result<Field> {
+    game_with_size(f, (f.width(), f.height(), f.total_mines()))
+}
+
+/// Returns a new game of the given width, height and number of mines, with the other settings of
+/// the field and a new random seed.
+pub fn game_with_size(
+    f: &Field,
+    (width, height, mines): (usize, usize, usize),
+) -> io::Result<Field> {
+    let new = Field::new(height, width, mines, f.safe_start())?;
+    Ok(with_settings_of(new, f))
+}
+
+/// Returns the game of the field once more, with the same settings and seed. Revealing the same
+/// first cell results in the same mines.
+pub fn same_game(f: &Field) -> io::Result<Field> {
+    let new = Field::with_seed(
+        f.height(),
+        f.width(),
+        f.total_mines(),
+        f.safe_start(),
+        f.seed(),
+    )?;
+    Ok(with_settings_of(new, f))
+}
+
+fn with_settings_of(mut new: Field, f: &Field) -> Field {
+    new.set_no_guess(f.no_guess() != NoGuess::Off);
+    new.set_practice(f.is_practice());
+    new
+}
+
+/// Parses the size of a new game as typed at the settings prompt: a difficulty preset like
+/// `expert`, or a width, height and number of mines like `20x10 50` or `20x10 15%`.
+pub fn parse_size(s: &str) -> io::Result<(usize, usize, usize)> {
+    let s = s.trim();
+    if let Some(size) = s.parse::<Difficulty>().ok().and_then(|d| d.size()) {
+        return Ok(size);
+    }
+
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "invalid settings '{}', expected beginner, intermediate, expert or a size like 20x10 50",
+                s
+            ),
+        )
+    };
+    let (size, mines) = s.split_once(' ').ok_or_else(invalid)?;
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let width: usize = width.parse().map_err(|_| invalid())?;
+    let height: usize = height.parse().map_err(|_| invalid())?;
+    let mines: Mines = mines
+        .trim()
+        .parse()
+        .map_err(|err: String| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+    Ok((width, height, mines.count(cell_count(width, height)?)))
+}
+
+/// Returns a short note on whether the field can be solved without guessing, to show after the
+/// status of the game.
+pub fn no_guess_note(f: &Field) -> &'static str {
+    match f.no_guess() {
+        NoGuess::Off => "",
+        NoGuess::On => " — no guessing",
+        NoGuess::FellBack => " — no guess-free field found",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(args: &[&str]) -> Result<GameConfig, structopt::clap::Error> {
+        GameConfig::from_iter_safe(std::iter::once("mine").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn new_field_settings_conflict_with_a_loaded_game() {
+        for settings in [
+            &["--seed", "7"][..],
+            &["--difficulty", "expert"],
+            &["--width", "20"],
+            &["--height", "20"],
+            &["--mines", "15%"],
+            &["--safe-start", "cell"],
+            &["--no-guess"],
+        ] {
+            for game in [&["--load", "game.save"][..], &["--resume"]] {
+                let args: Vec<&str> = game.iter().chain(settings).copied().collect();
+                assert!(config(&args).is_err(), "{:?} is accepted", args);
+            }
+        }
+
+        assert!(config(&["--load", "game.save", "--practice"]).is_ok());
+        assert!(config(&["--resume", "--practice"]).is_ok());
+    }
+
+    #[test]
+    fn sizes_of_presets_and_custom_fields() {
+        let size = |args: &[&str]| config(args).unwrap().size();
+        assert_eq!(size(&[]).unwrap(), (9, 9, 10));
+        assert_eq!(size(&["-d", "expert"]).unwrap(), (30, 16, 99));
+        assert_eq!(size(&["-w", "20"]).unwrap(), (20, 9, 10));
+        assert_eq!(
+            size(&["-w", "20", "-h", "10", "-m", "15%"]).unwrap(),
+            (20, 10, 30)
+        );
+        assert_eq!(size(&["-d", "custom", "-m", "5"]).unwrap(), (9, 9, 5));
+
+        let err = size(&["-d", "expert", "-w", "20"]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        for args in [
+            &["--difficulty", "hard"][..],
+            &["--mines", "many"],
+            &["--mines", "150%"],
+            &["--safe-start", "maybe"],
+        ] {
+            assert!(config(args).is_err(), "{:?} is accepted", args);
+        }
+
+        for args in [
+            &["--no-guess", "--safe-start", "off"][..],
+            &["--no-guess", "--safe-start", "cell"],
+            &["-w", "0"],
+            &["-w", "3", "-h", "3", "-m", "10"],
+        ] {
+            assert!(
+                config(args).unwrap().field().is_err(),
+                "{:?} is accepted",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn sizes_typed_at_the_prompt() {
+        assert_eq!(parse_size("intermediate").unwrap(), (16, 16, 40));
+        assert_eq!(parse_size(" 20x10 50 ").unwrap(), (20, 10, 50));
+        assert_eq!(parse_size("20x10 15%").unwrap(), (20, 10, 30));
+        for size in ["custom", "20x10", "20 10 50", "x10 5", "20x10 lots"] {
+            let err = parse_size(size).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{}", size);
+        }
+    }
+}
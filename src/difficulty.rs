@@ -0,0 +1,168 @@
+//! The classic difficulty presets, and the number of mines as a count or a percentage.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A difficulty preset, which sets the size of the field and its number of mines.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difficulty {
+    /// A field of 9x9 cells with 10 mines.
+    Beginner,
+    /// A field of 16x16 cells with 40 mines.
+    Intermediate,
+    /// A field of 30x16 cells with 99 mines.
+    Expert,
+    /// A field of any size, with any number of mines.
+    Custom,
+}
+
+impl Difficulty {
+    /// Returns the width, height and number of mines of the preset, or `None` for
+    /// `Difficulty::Custom`.
+    pub fn size(&self) -> Option<(usize, usize, usize)> {
+        match self {
+            Self::Beginner => Some((9, 9, 10)),
+            Self::Intermediate => Some((16, 16, 40)),
+            Self::Expert => Some((30, 16, 99)),
+            Self::Custom => None,
+        }
+    }
+
+    /// Returns the preset with the given width, height and number of mines, or
+    /// `Difficulty::Custom` if there is none.
+    pub fn of(width: usize, height: usize, mines: usize) -> Self {
+        [Self::Beginner, Self::Intermediate, Self::Expert]
+            .into_iter()
+            .find(|preset| preset.size() == Some((width, height, mines)))
+            .unwrap_or(Self::Custom)
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Beginner => write!(f, "beginner"),
+            Self::Intermediate => write!(f, "intermediate"),
+            Self::Expert => write!(f, "expert"),
+            Self::Custom => write!(f, "custom"),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beginner" => Ok(Self::Beginner),
+            "intermediate" => Ok(Self::Intermediate),
+            "expert" => Ok(Self::Expert),
+            "custom" => Ok(Self::Custom),
+            _ => Err(format!(
+                "invalid difficulty '{}', expected one of: beginner, intermediate, expert, custom",
+                s
+            )),
+        }
+    }
+}
+
+/// The number of mines in a field, either as a count or as a percentage of its cells.
+///
+/// Written and read as a plain number for a count, like `40`, or with a percent sign for a
+/// percentage, like `15.5%`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mines {
+    /// A fixed number of mines.
+    Count(usize),
+    /// A percentage of the cells, between 0 and 100.
+    Percentage(f64),
+}
+
+impl Mines {
+    /// Returns the number of mines in a field of `cells` cells. A percentage is rounded to the
+    /// nearest number of mines.
+    pub fn count(&self, cells: usize) -> usize {
+        match *self {
+            Self::Count(count) => count,
+            Self::Percentage(percentage) => (cells as f64 * percentage / 100.0).round() as usize,
+        }
+    }
+}
+
+impl fmt::Display for Mines {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Count(count) => write!(f, "{}", count),
+            Self::Percentage(percentage) => write!(f, "{}%", percentage),
+        }
+    }
+}
+
+impl FromStr for Mines {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('%') {
+            Some(percentage) => match percentage.trim().parse::<f64>() {
+                Ok(percentage) if (0.0..=100.0).contains(&percentage) => {
+                    Ok(Self::Percentage(percentage))
+                }
+                _ => Err(format!(
+                    "invalid percentage of mines '{}', expected a number from 0 to 100",
+                    s
+                )),
+            },
+            None => s.parse().map(Self::Count).map_err(|_| {
+                format!(
+                    "invalid number of mines '{}', expected a count like 40 or a percentage like 15%",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn difficulties_are_read_as_written() {
+        for difficulty in [
+            Difficulty::Beginner,
+            Difficulty::Intermediate,
+            Difficulty::Expert,
+            Difficulty::Custom,
+        ] {
+            assert_eq!(difficulty.to_string().parse(), Ok(difficulty));
+        }
+        assert!("Expert".parse::<Difficulty>().is_err());
+        assert!("".parse::<Difficulty>().is_err());
+
+        assert_eq!(Difficulty::of(16, 16, 40), Difficulty::Intermediate);
+        assert_eq!(Difficulty::of(16, 16, 41), Difficulty::Custom);
+    }
+
+    #[test]
+    fn mines_as_a_count_or_a_percentage() {
+        assert_eq!("40".parse(), Ok(Mines::Count(40)));
+        assert_eq!("15.5%".parse(), Ok(Mines::Percentage(15.5)));
+        assert_eq!("100 %".parse(), Ok(Mines::Percentage(100.0)));
+        for mines in ["-1", "4.5", "lots", "%", "-5%", "100.1%", "NaN%"] {
+            assert!(mines.parse::<Mines>().is_err(), "{} is accepted", mines);
+        }
+
+        for mines in [Mines::Count(40), Mines::Percentage(15.5)] {
+            assert_eq!(mines.to_string().parse(), Ok(mines));
+        }
+    }
+
+    #[test]
+    fn percentages_are_rounded_to_a_count() {
+        assert_eq!(Mines::Count(40).count(10), 40);
+        assert_eq!(Mines::Percentage(15.0).count(200), 30);
+        assert_eq!(Mines::Percentage(12.5).count(81), 10);
+        assert_eq!(Mines::Percentage(100.0).count(81), 81);
+        assert_eq!(Mines::Percentage(0.0).count(81), 0);
+    }
+}
@@ -1,55 +1,482 @@
+//! The play field, and the actions that can be applied to it.
+
 use crate::cell::Cell;
-use rand::prelude::{thread_rng, SliceRandom};
-use std::collections::HashMap;
+use crate::clock::Clock;
+use crate::history::{Change, History};
+use crate::rng::Rng;
+use crate::solver;
+use crate::stats::{Clicks, Regions};
+use std::collections::VecDeque;
 use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+mod save;
+
+/// The longest time spent on looking for a layout that can be solved without guessing, before
+/// falling back to a random layout.
+const NO_GUESS_TIME_LIMIT: Duration = Duration::from_secs(2);
+
+/// Above this fraction of mines, layouts that can be solved without guessing are too rare to look
+/// for, and a random layout is placed right away.
+const NO_GUESS_MAX_DENSITY: f64 = 0.25;
 
 /// The _Field_ class represents the play field made up of cells.
 #[derive(Clone, PartialEq, Eq)]
 pub struct Field {
     height: usize,
     width: usize,
+    mines: usize,
+    seed: u64,
     game_over: bool, // default = false
     cells: Vec<Cell>,
     cursor_pos: (usize, usize), // default = (x: 0, y: 0)
+
+    /// Which cells are kept free of mines on the first reveal.
+    safe_start: SafeStart,
+    /// Mines are only placed once the first cell is revealed, unless `safe_start` is
+    /// `SafeStart::Off`.
+    mines_placed: bool,
+    /// Whether the mines are placed such that the field can be solved without guessing.
+    no_guess: NoGuess,
+
+    /// The undo/redo history of the flags and reveals.
+    history: History,
+    /// In practice mode, a losing reveal can be undone.
+    practice: bool,
+
+    /// Runs from the first reveal until the game is over or won.
+    clock: Clock,
+    /// The flags and reveals applied with `Field::apply_action`.
+    clicks: Clicks,
+
+    /// The cell highlighted by the last hint, which is certainly a mine.
+    highlight: Option<(usize, usize)>,
+    /// The openings and numbers of the mine layout that make up its 3BV, empty until the mines
+    /// are placed.
+    three_bv: Regions,
+}
+
+/// Returns the number of cells of a field of `width` by `height` cells, or an error if it has no
+/// cells or too many to count.
+pub fn cell_count(width: usize, height: usize) -> Result<usize, FieldError> {
+    if width == 0 || height == 0 {
+        return Err(FieldError::Empty { width, height });
+    }
+    width
+        .checked_mul(height)
+        .ok_or(FieldError::TooLarge { width, height })
+}
+
+/// Determines which cells are guaranteed to be free of mines when the first cell is revealed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SafeStart {
+    /// Mines are placed when the field is created. The first reveal may hit a mine.
+    Off,
+    /// The first revealed cell never contains a mine.
+    Cell,
+    /// The first revealed cell and its neighbours never contain a mine, so the first reveal
+    /// always opens up an area.
+    Opening,
+}
+
+impl fmt::Display for SafeStart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Cell => write!(f, "cell"),
+            Self::Opening => write!(f, "opening"),
+        }
+    }
+}
+
+impl FromStr for SafeStart {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "cell" => Ok(Self::Cell),
+            "opening" => Ok(Self::Opening),
+            _ => Err(format!(
+                "invalid safe start '{}', expected one of: off, cell, opening",
+                s
+            )),
+        }
+    }
+}
+
+/// Whether the mines are placed such that the field can be solved without guessing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NoGuess {
+    /// The mines are placed at random.
+    Off,
+    /// The mines are placed such that the solver can clear the field from the first reveal,
+    /// without ever having to guess.
+    On,
+    /// No layout that can be solved without guessing was found in time, so the mines were placed
+    /// at random after all.
+    FellBack,
+}
+
+impl fmt::Display for NoGuess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::On => write!(f, "on"),
+            Self::FellBack => write!(f, "fell-back"),
+        }
+    }
+}
+
+impl FromStr for NoGuess {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "on" => Ok(Self::On),
+            "fell-back" => Ok(Self::FellBack),
+            _ => Err(format!(
+                "invalid no guess setting '{}', expected one of: off, on, fell-back",
+                s
+            )),
+        }
+    }
+}
+
+/// The reason a field cannot be created.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FieldError {
+    /// The field has no columns or no rows.
+    Empty {
+        /// The requested number of columns.
+        width: usize,
+        /// The requested number of rows.
+        height: usize,
+    },
+    /// There are more mines than cells to place them on.
+    TooManyMines {
+        /// The requested number of mines.
+        mines: usize,
+        /// The number of cells of the field.
+        cells: usize,
+    },
+    /// The field has more cells than can be counted.
+    TooLarge {
+        /// The requested number of columns.
+        width: usize,
+        /// The requested number of rows.
+        height: usize,
+    },
+    /// A mine layout does not hold one value for every cell.
+    LayoutSize {
+        /// The number of cells of the field.
+        expected: usize,
+        /// The number of cells in the layout.
+        found: usize,
+    },
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty { width, height } => write!(
+                f,
+                "a field of {}x{} has no cells, the width and height must be at least 1",
+                width, height
+            ),
+            Self::TooLarge { width, height } => {
+                write!(f, "a field of {}x{} has too many cells", width, height)
+            }
+            Self::TooManyMines { mines, cells } => write!(
+                f,
+                "{} mines do not fit in a field of {} cells",
+                mines, cells
+            ),
+            Self::LayoutSize { expected, found } => write!(
+                f,
+                "the layout holds {} cells, but the field has {}",
+                found, expected
+            ),
+        }
+    }
 }
 
+impl std::error::Error for FieldError {}
+
+impl From<FieldError> for io::Error {
+    fn from(err: FieldError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, err)
+    }
+}
+
+/// An edge of the field, which the cursor can be moved to.
 pub enum Edge {
+    /// The leftmost column.
     Left,
+    /// The rightmost column.
     Right,
+    /// The top row.
     Up,
+    /// The bottom row.
     Down,
 }
 
 impl Field {
-    /// Initialises the array of cells with a (pseudo) random distribution of mines.
-    pub fn new(height: usize, width: usize, mines: usize) -> Self {
-        let mine_cells = vec![true; mines];
-        let non_mine_cells = vec![false; height * width - mines];
-
-        let mut rng = thread_rng();
-        let mut is_mine_vec = [mine_cells, non_mine_cells].concat();
-        is_mine_vec.shuffle(&mut rng);
+    /// Initialises the array of cells with a random seed. See `Field::with_seed`.
+    pub fn new(
+        height: usize,
+        width: usize,
+        mines: usize,
+        safe_start: SafeStart,
+    ) -> Result<Self, FieldError> {
+        Self::with_seed(height, width, mines, safe_start, rand::random())
+    }
 
-        let cells = is_mine_vec
-            .iter()
-            .map(|m| match m {
-                true => Cell::new_mine(),
-                false => Cell::new(),
-            })
-            .collect();
+    /// Initialises the array of cells. The (pseudo) random distribution of mines is placed right
+    /// away if `safe_start` is `SafeStart::Off`. Otherwise, it is deferred until the first cell is
+    /// revealed, such that the mines can be placed around it.
+    ///
+    /// The distribution of mines is derived from the `seed` only, using the stable generator in
+    /// `crate::rng`. The same seed, dimensions, number of mines and safe cells always result in
+    /// the same mine layout. With no-guess generation, this only holds as long as a layout that
+    /// can be solved is found within the time limit. See `Field::set_no_guess`.
+    ///
+    /// Returns an error if the field has no cells, or if the mines do not fit in it.
+    pub fn with_seed(
+        height: usize,
+        width: usize,
+        mines: usize,
+        safe_start: SafeStart,
+        seed: u64,
+    ) -> Result<Self, FieldError> {
+        let cells = cell_count(width, height)?;
+        if mines > cells {
+            return Err(FieldError::TooManyMines { mines, cells });
+        }
 
         let mut f = Self {
-            height: height.max(1),
-            width: width.max(1),
+            height,
+            width,
+            mines,
+            seed,
             game_over: false,
             cursor_pos: (0, 0),
-            cells,
+            cells: vec![Cell::new(); cells],
+            safe_start,
+            mines_placed: false,
+            no_guess: NoGuess::Off,
+            history: History::new(),
+            practice: false,
+            clock: Clock::default(),
+            clicks: Clicks::default(),
+            highlight: None,
+            three_bv: Regions::default(),
         };
+
+        if safe_start == SafeStart::Off {
+            f.place_mines(&[], seed);
+        }
+
+        Ok(f)
+    }
+
+    /// Initialises the field with the mines at the given layout, in which `true` marks a mine.
+    /// The layout holds the cells row by row, and must contain `height * width` cells. Returns an
+    /// error if it does not, or if the field has no cells.
+    ///
+    /// The `seed` and `safe_start` are kept for reference only, since the mines have already been
+    /// placed.
+    pub fn from_layout(
+        height: usize,
+        width: usize,
+        layout: &[bool],
+        safe_start: SafeStart,
+        seed: u64,
+    ) -> Result<Self, FieldError> {
+        let mut f = Self::with_seed(height, width, 0, safe_start, seed)?;
+        if layout.len() != height * width {
+            return Err(FieldError::LayoutSize {
+                expected: height * width,
+                found: layout.len(),
+            });
+        }
+
+        for (cell, &m) in f.cells.iter_mut().zip(layout) {
+            cell.mine = m;
+        }
+        f.mines = layout.iter().filter(|&&m| m).count();
+        f.mines_placed = true;
+        f.initialize_neighbours();
+        f.three_bv = Regions::of(&f);
+
+        Ok(f)
+    }
+
+    /// Initialises the field from a picture of its rows, in which `*` marks a mine and any other
+    /// character a cell without one, like `["..*", "..."]`. This is a shorthand for
+    /// `Field::from_layout` without a seed, for tests and examples.
+    ///
+    /// Returns an error if the rows are not all of the same length, or if there are none.
+    pub fn from_rows(rows: &[&str]) -> Result<Self, FieldError> {
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        let layout: Vec<bool> = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|c| c == '*')
+            .collect();
+        if rows.iter().any(|row| row.chars().count() != width) {
+            return Err(FieldError::LayoutSize {
+                expected: rows.len() * width,
+                found: layout.len(),
+            });
+        }
+        Self::from_layout(rows.len(), width, &layout, SafeStart::Off, 0)
+    }
+
+    /// Returns `true` once the mines have been placed, which happens on the first reveal.
+    pub fn mines_placed(&self) -> bool {
+        self.mines_placed
+    }
+
+    /// Returns the openings and numbers of the mine layout that make up its 3BV.
+    pub(crate) fn three_bv_regions(&self) -> &Regions {
+        &self.three_bv
+    }
+
+    /// Returns the layout of the mines row by row, in which `true` marks a mine. Returns `None` if
+    /// the mines have not been placed yet.
+    pub fn mine_layout(&self) -> Option<Vec<bool>> {
+        if !self.mines_placed {
+            return None;
+        }
+
+        Some(self.cells.iter().map(|cell| cell.is_mine()).collect())
+    }
+
+    /// Distributes the mines over the field using the `seed`, keeping the cells at the `safe`
+    /// indices free of mines.
+    fn place_mines(&mut self, safe: &[usize], seed: u64) {
+        let candidates: Vec<usize> = (0..self.cells.len())
+            .filter(|index| !safe.contains(index))
+            .collect();
+
+        let mine_cells = vec![true; self.mines];
+        let non_mine_cells = vec![false; candidates.len() - self.mines];
+
+        let mut rng = Rng::new(seed);
+        let mut is_mine_vec = [mine_cells, non_mine_cells].concat();
+        rng.shuffle(&mut is_mine_vec);
+
+        for (&index, &m) in candidates.iter().zip(is_mine_vec.iter()) {
+            self.cells[index].mine = m;
+        }
+        self.mines_placed = true;
+
         // Call the `initializeNeighbours()` function to populate the cells in the cells array with
         // the proper neigbour count. The neigbour count represents the number of neigbouring
         // mines.
-        f.initialize_neighbours();
-        f
+        self.initialize_neighbours();
+        self.three_bv = Regions::of(self);
+    }
+
+    /// Places the mines around the first revealed cell at the given position, according to the
+    /// field's `SafeStart` setting.
+    ///
+    /// If there are too many mines to keep the whole neighbourhood free, only the revealed cell
+    /// itself is kept free. If even that is not possible, the field is filled with mines.
+    fn place_mines_around(&mut self, x: usize, y: usize) {
+        let start = y * self.width + x;
+        let mut safe = vec![start];
+        if self.safe_start == SafeStart::Opening {
+            safe = self.neighbourhood(start).collect();
+        }
+
+        if self.cells.len() - safe.len() < self.mines {
+            safe = vec![start];
+        }
+        if self.cells.len() - safe.len() < self.mines {
+            safe.clear();
+        }
+
+        if self.no_guess == NoGuess::On {
+            self.place_mines_without_guessing(&safe, start)
+        } else {
+            self.place_mines(&safe, self.seed)
+        }
+    }
+
+    /// Places the mines such that the solver can clear the field from the first reveal at the
+    /// `start` index, without guessing. Layouts are generated from seeds derived from the field's
+    /// seed, until the solver clears one of them.
+    ///
+    /// If no such layout is found within `NO_GUESS_TIME_LIMIT`, or if the mines are too dense to
+    /// look for one, the mines are placed at random like usual.
+    fn place_mines_without_guessing(&mut self, safe: &[usize], start: usize) {
+        let density = self.mines as f64 / self.cells.len() as f64;
+        if !safe.is_empty() && density <= NO_GUESS_MAX_DENSITY {
+            let deadline = Instant::now() + NO_GUESS_TIME_LIMIT;
+            let mut seeds = Rng::new(self.seed);
+            let mut seed = self.seed;
+            while Instant::now() < deadline {
+                self.place_mines(safe, seed);
+                if self.is_solvable_from(start, deadline) {
+                    return;
+                }
+                seed = seeds.next_u64();
+            }
+        }
+
+        self.no_guess = NoGuess::FellBack;
+        self.place_mines(safe, self.seed)
+    }
+
+    /// Returns `true` if the solver can clear the field after revealing the cell at the `start`
+    /// index, without guessing. Returns `false` as soon as the deadline passes. The field itself is
+    /// left untouched.
+    fn is_solvable_from(&self, start: usize, deadline: Instant) -> bool {
+        let mut f = self.clone();
+        f.flood_reveal(&[start]);
+
+        loop {
+            if f.game_over {
+                return false;
+            }
+            if f.has_won() {
+                return true;
+            }
+
+            let deductions = match solver::solve_until(&f, deadline) {
+                Some(deductions) => deductions,
+                None => return false,
+            };
+            if deductions.safe.is_empty() {
+                return false;
+            }
+
+            // Flag the mines, such that the solver does not have to find them again.
+            for &(x, y) in &deductions.mines {
+                f.cells[y * f.width + x].toggle_flagged();
+            }
+            let safe: Vec<usize> = deductions
+                .safe
+                .iter()
+                .map(|&(x, y)| y * f.width + x)
+                .collect();
+            f.flood_reveal(&safe);
+        }
+    }
+
+    /// Returns the indices of the cell at the given _index_ and its neighbours within the field.
+    pub(crate) fn neighbourhood(&self, index: usize) -> impl Iterator<Item = usize> {
+        let width = self.width;
+        let (x, y) = (index % width, index / width);
+        let xs = x.saturating_sub(1)..=(x + 1).min(width - 1);
+
+        (y.saturating_sub(1)..=(y + 1).min(self.height - 1))
+            .flat_map(move |ny| xs.clone().map(move |nx| ny * width + nx))
     }
 
     /// Returns the total number of flags in the field.
@@ -57,9 +484,71 @@ impl Field {
         self.cells.iter().filter(|c| c.is_flagged()).count()
     }
 
-    /// Returns the total number of mines in the field.
+    /// Returns the total number of mines in the field, including mines that have not been placed
+    /// yet.
     pub fn total_mines(&self) -> usize {
-        self.cells.iter().filter(|c| c.is_mine()).count()
+        self.mines
+    }
+
+    /// Enables or disables practice mode. In practice mode, a losing reveal can be undone.
+    pub fn set_practice(&mut self, practice: bool) {
+        self.practice = practice
+    }
+
+    /// Enables no-guess generation, in which the mines are placed such that the field can be
+    /// solved from the first reveal without guessing. See `NoGuess`.
+    ///
+    /// This only has an effect if the mines have not been placed yet, so not with
+    /// `SafeStart::Off`. With `SafeStart::Cell`, the first reveal rarely opens up an area to solve
+    /// the field from, so the mines mostly fall back to a random layout.
+    pub fn set_no_guess(&mut self, no_guess: bool) {
+        if !self.mines_placed {
+            self.no_guess = if no_guess { NoGuess::On } else { NoGuess::Off };
+        }
+    }
+
+    /// Returns whether the mines are placed such that the field can be solved without guessing.
+    pub fn no_guess(&self) -> NoGuess {
+        self.no_guess
+    }
+
+    /// Returns `true` if the field is in practice mode.
+    pub fn is_practice(&self) -> bool {
+        self.practice
+    }
+
+    /// Returns the time spent playing, from the first reveal until the game is over or won.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    /// Returns `true` while the clock runs: from the first reveal until the game is over or won.
+    pub fn is_timing(&self) -> bool {
+        self.game_state() == GameState::Running && self.mines_placed
+    }
+
+    /// Returns the flags and reveals applied to the field with `Field::apply_action`.
+    pub fn clicks(&self) -> Clicks {
+        self.clicks
+    }
+
+    /// Runs the clock while the game is in progress, and stops it once the game is over or won.
+    fn update_clock(&mut self) {
+        if self.mines_placed && !self.game_over && !self.has_won() {
+            self.clock.start()
+        } else {
+            self.clock.stop()
+        }
+    }
+
+    /// Returns which cells are kept free of mines on the first reveal.
+    pub fn safe_start(&self) -> SafeStart {
+        self.safe_start
+    }
+
+    /// Returns the seed from which the mine layout is generated.
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 
     /// Returns the number of mines in the field subtracted by the number of flags placed.
@@ -74,6 +563,7 @@ impl Field {
         self.cells[index].clone()
     }
 
+    /// Returns all cells of the field, row by row.
     pub fn cells(&self) -> &Vec<Cell> {
         &self.cells
     }
@@ -82,11 +572,17 @@ impl Field {
         &mut self.cells
     }
 
+    /// Returns the number of columns of the field.
     pub fn width(&self) -> usize {
         self.width
     }
 
-    /// Returns y value of the cursor position in the field.
+    /// Returns the number of rows of the field.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns x value of the cursor position in the field.
     pub fn cursor_pos_x(&self) -> usize {
         self.cursor_pos.0
     }
@@ -99,137 +595,124 @@ impl Field {
     /// This function will reveal the cell at the specified position. If the
     /// cell does not have neighbours, it will also reveal its neighbours.
     ///
-    /// Note that this means this is a recursive function depending on the cell
-    /// that was called.
-    ///
     /// If, however, the cell contains a mine, the field's `gameOver` variable
     /// will be set to `true`, signalling the end of the game.
     ///
     /// Because of this implementation, this function recreates the typical
-    /// flood fill behaviour seen in many minesweepers.
-    pub fn reveal(&mut self, x: usize, y: usize) {
+    /// flood fill behaviour seen in many minesweepers. See `Field::flood_reveal`.
+    ///
+    /// If this is the first cell to be revealed, the mines are placed around it first.
+    ///
+    /// Returns the positions of all cells that were revealed.
+    pub fn reveal(&mut self, x: usize, y: usize) -> Vec<(usize, usize)> {
         let index = y * self.width + x;
         if self.cells[index].is_flagged() {
             // If flagged, the cell cannot be revealed. That's the whole point kinda.
-            return;
+            return Vec::new();
         }
 
-        // Cell is not flagged. Reveal the cell.
-        self.cells[index].reveal();
+        if !self.mines_placed {
+            self.place_mines_around(x, y);
+        }
+
+        self.flood_reveal(&[index])
+    }
 
-        // If the cell contains a mine... BOOOOM!!!! The game is over :(
-        if self.cells[index].is_mine() {
-            self.game_over = true
+    /// Reveals the cells at the `start` indices and floods outwards from them.
+    ///
+    /// If a revealed cell is not surrounded by any mines, the number of neighbours is zero. In
+    /// that case, every neighbour is revealed as well. The result of this behaviour is that once a
+    /// zero cell has been revealed, the whole area of zeroes and its adjacent cells will be
+    /// revealed.
+    ///
+    /// The flood fill keeps an explicit queue of cells to expand instead of recursing, so it
+    /// works on fields of any size. A cell is marked revealed as it is queued, so it is queued at
+    /// most once and the queue never holds more than all cells of the field.
+    ///
+    /// Returns the positions of all cells that were revealed, in the order they were revealed.
+    fn flood_reveal(&mut self, start: &[usize]) -> Vec<(usize, usize)> {
+        let mut revealed = Vec::new();
+        let mut queue = VecDeque::new();
+
+        for &index in start {
+            // Flagged cells cannot be revealed, and revealed cells do not need to be.
+            if self.cells[index].is_flagged() || self.cells[index].is_revealed() {
+                continue;
+            }
+            self.cells[index].reveal();
+            queue.push_back(index);
         }
 
-        // If the current cell is not surrounded by any mines, the number of
-        // neighbours is zero. In that case, reveal every neigbour.
-        //
-        // The result of this behaviour is that once a zero cell has been
-        // revealed, the whole field of zeroes and its adjacent cells will be
-        // revealed. Therefore this implements the typical flood fill
-        // behaviour seen in minesweeper.
-        if self.cells[index].neighbours() == 0 {
-            for y_offset in -1..=1 {
-                // Make sure the selected cell is within vertical bounds.
-                if y as isize + y_offset < 0 || y as isize + y_offset >= self.height as isize {
-                    continue;
-                }
+        while let Some(index) = queue.pop_front() {
+            revealed.push((index % self.width, index / self.width));
+
+            let cell = &self.cells[index];
+            // If the cell contains a mine... BOOOOM!!!! The game is over :(
+            if cell.is_mine() {
+                self.game_over = true;
+                continue;
+            }
+
+            // A flagged zero cell is revealed by its neighbour, but does not expand any further.
+            if cell.neighbours() != 0 || cell.is_flagged() {
+                continue;
+            }
 
-                for x_offset in -1..=1 {
-                    // Make sure the selected cell is within horizontal bounds.
-                    if x as isize + x_offset < 0 || x as isize + x_offset >= self.width as isize {
-                        continue;
-                    }
-
-                    // If cell has already been revealed, do nothing.
-                    // Else, reveal the selected cell. (Note that this
-                    // will recursively call this function.)
-                    if self
-                        .get_cell(
-                            (x as isize + x_offset) as usize,
-                            (y as isize + y_offset) as usize,
-                        )
-                        .is_revealed()
-                    {
-                        continue;
-                    } else {
-                        let i =
-                            index as isize + x_offset as isize + (y_offset * self.width as isize);
-                        // Reveal the current cell
-                        self.cells[i as usize].reveal();
-
-                        // Recursively call this function on all neighbours of
-                        // the initial cell.
-                        self.reveal(
-                            (x as isize + x_offset) as usize,
-                            (y as isize + y_offset) as usize,
-                        );
-                    }
+            for neighbour in self.neighbourhood(index) {
+                if !self.cells[neighbour].is_revealed() {
+                    self.cells[neighbour].reveal();
+                    queue.push_back(neighbour);
                 }
             }
         }
+
+        self.update_clock();
+        revealed
     }
 
     /// This function reveals all cells in the field.
-    pub fn reveal_all(&mut self) {
-        for cell in self.cells_mut() {
-            cell.reveal()
+    ///
+    /// Returns the events of the cells that were hidden until now.
+    pub fn reveal_all(&mut self) -> Vec<Event> {
+        let width = self.width;
+        let mut events = Vec::new();
+        for (index, cell) in self.cells_mut().iter_mut().enumerate() {
+            if !cell.is_revealed() {
+                cell.reveal();
+                events.push(Event::CellRevealed(index % width, index / width));
+            }
         }
+        events
     }
 
     /// Reveals the field from a given cell, expanding around cells where 0 neighbours are
-    /// encountered.
-    pub fn reveal_from_cell(&mut self, x: usize, y: usize) {
-        // struct Pos: Hashable {
-        //     var x: Int
-        //     var y: Int
-        // }
-        type Pos = (usize, usize); // (x, y)
+    /// encountered. This only happens when as many flags as mines surround the cell.
+    ///
+    /// Returns the positions of all cells that were revealed.
+    pub fn reveal_from_cell(&mut self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        if !self.mines_placed {
+            // Nothing has been revealed yet, so there is nothing to reveal around.
+            return Vec::new();
+        }
 
         // index - w - 1    index - w     index - w + 1
         // index - 1        .             index + 1
         // index + w - 1    index + w     index + w + 1
-        let mut adjacent_cells: HashMap<Pos, Cell> = HashMap::new();
-
-        let mut mines = 0;
-        let mut flags = 0;
-        for y_offset in -1..=1 {
-            if y as isize + y_offset < 0 || y as isize + y_offset >= self.height as isize {
-                continue;
-            }
-
-            for x_offset in -1..=1 {
-                if x as isize + x_offset < 0 || x as isize + x_offset >= self.width as isize {
-                    continue;
-                }
+        let adjacent_cells: Vec<usize> = self.neighbourhood(y * self.width + x).collect();
 
-                let cell = self.get_cell(
-                    (x as isize + x_offset) as usize,
-                    (y as isize + y_offset) as usize,
-                );
-                adjacent_cells.insert(
-                    (
-                        (x as isize + x_offset) as usize,
-                        (y as isize + y_offset) as usize,
-                    ),
-                    cell.clone(),
-                );
-
-                if cell.is_mine() {
-                    mines += 1
-                }
-                if cell.is_flagged() {
-                    flags += 1
-                }
-            }
-        }
+        let mines = adjacent_cells
+            .iter()
+            .filter(|&&i| self.cells[i].is_mine())
+            .count();
+        let flags = adjacent_cells
+            .iter()
+            .filter(|&&i| self.cells[i].is_flagged())
+            .count();
 
         if mines == flags {
-            for element in adjacent_cells.iter().filter(|e| !e.1.is_flagged()) {
-                let p = element.0;
-                self.reveal(p.0, p.1)
-            }
+            self.flood_reveal(&adjacent_cells)
+        } else {
+            Vec::new()
         }
     }
 
@@ -243,31 +726,9 @@ impl Field {
     /// This function returns an integer value representing the number of
     /// neighbouring mines for a given _index_.
     fn count_neighbours(&self, index: usize) -> usize {
-        // index - w - 1    index - w     index - w + 1
-        // index - 1        .             index + 1
-        // index + w - 1    index + w     index + w + 1
-        let mut count = 0;
-        let y: isize = (index / self.width) as isize;
-        let x: isize = (index % self.width) as isize;
-
-        for y_offset in -1..=1 {
-            if y + y_offset < 0 || y + y_offset >= self.height as isize {
-                continue;
-            }
-
-            for x_offset in -1..=1 {
-                if x + x_offset < 0 || x + x_offset >= self.width as isize {
-                    continue;
-                }
-
-                let cell = self.get_cell((x + x_offset) as usize, (y + y_offset) as usize);
-                if cell.is_mine() {
-                    count += 1
-                }
-            }
-        }
-
-        count
+        self.neighbourhood(index)
+            .filter(|&i| self.cells[i].is_mine())
+            .count()
     }
 
     /// This function will iterate over every cell in the field and write the count of neighbouring
@@ -295,6 +756,7 @@ impl Field {
     }
 
     #[inline]
+    /// Moves the cursor horizontally by the translation, unless that would leave the field.
     pub fn translate_x(&mut self, translation: isize) {
         if self.valid_translation(translation, 0) {
             self.cursor_pos.0 = (self.cursor_pos_x() as isize + translation) as usize
@@ -302,13 +764,22 @@ impl Field {
     }
 
     #[inline]
+    /// Moves the cursor vertically by the translation, unless that would leave the field.
     pub fn translate_y(&mut self, translation: isize) {
         if self.valid_translation(0, translation) {
             self.cursor_pos.1 = (self.cursor_pos_y() as isize + translation) as usize
         }
     }
 
+    /// Moves the cursor to the cell at the column and row, unless it is outside of the field.
+    pub fn move_cursor_to(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            self.cursor_pos = (x, y)
+        }
+    }
+
     #[inline]
+    /// Moves the cursor to the edge of the field, keeping its other coordinate.
     pub fn move_cursor_to_edge(&mut self, edge: Edge) {
         match edge {
             Edge::Left => self.cursor_pos.0 = 0,
@@ -348,24 +819,283 @@ impl Field {
     }
 }
 
+/// An input of the player, which is applied to the field with `Field::apply_action`.
+///
+/// Actions are written and read by name, like `cursor-up` or `reveal-around`. Moving the cursor
+/// to a cell is written with its column and row, like `cursor-to 3 4`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Action {
+    /// Moves the cursor one row up.
     CursorUp,
+    /// Moves the cursor one row down.
     CursorDown,
+    /// Moves the cursor one column to the left.
     CursorLeft,
+    /// Moves the cursor one column to the right.
     CursorRight,
 
+    /// Moves the cursor to the top row.
     CursorToEdgeUp,
+    /// Moves the cursor to the bottom row.
     CursorToEdgeDown,
+    /// Moves the cursor to the leftmost column.
     CursorToEdgeLeft,
+    /// Moves the cursor to the rightmost column.
     CursorToEdgeRight,
+    /// Moves the cursor to the cell at the column and row, unless it is outside of the field.
+    CursorTo(usize, usize),
 
+    /// Toggles the flag of the cell under the cursor.
     Flag,
+    /// Reveals the cell under the cursor.
     Reveal,
+    /// Reveals the neighbours of the cell under the cursor, if it touches as many flags as mines.
     RevealAround,
+
+    /// Undoes the last flag or reveal.
+    Undo,
+    /// Redoes the last undone flag or reveal.
+    Redo,
+
+    /// Gives a hint. See `Field::hint`.
+    Hint,
+}
+
+/// A change to the field, as returned by `Field::apply_action`.
+///
+/// Frontends can redraw only what changed, and statistics, replays and sounds can follow the game
+/// without comparing fields. Cells are given by their column and row.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Event {
+    /// The cell was revealed.
+    CellRevealed(usize, usize),
+    /// The cell was hidden again, by undoing its reveal.
+    CellHidden(usize, usize),
+    /// The cell was flagged.
+    CellFlagged(usize, usize),
+    /// The flag was taken off the cell.
+    CellUnflagged(usize, usize),
+    /// The revealed cell is a mine, which lost the game.
+    MineHit(usize, usize),
+    /// The last cell without a mine was revealed, which won the game.
+    GameWon,
+    /// The cursor moved to the cell.
+    CursorMoved(usize, usize),
+    /// A hint highlighted the cell, which is certainly a mine.
+    MineHighlighted(usize, usize),
+    /// The highlighted mine is no longer highlighted.
+    HighlightCleared,
+}
+
+impl Action {
+    /// All actions, paired with the names they are written and read as.
+    const NAMES: [(Action, &'static str); 14] = [
+        (Action::CursorUp, "cursor-up"),
+        (Action::CursorDown, "cursor-down"),
+        (Action::CursorLeft, "cursor-left"),
+        (Action::CursorRight, "cursor-right"),
+        (Action::CursorToEdgeUp, "cursor-to-edge-up"),
+        (Action::CursorToEdgeDown, "cursor-to-edge-down"),
+        (Action::CursorToEdgeLeft, "cursor-to-edge-left"),
+        (Action::CursorToEdgeRight, "cursor-to-edge-right"),
+        (Action::Flag, "flag"),
+        (Action::Reveal, "reveal"),
+        (Action::RevealAround, "reveal-around"),
+        (Action::Undo, "undo"),
+        (Action::Redo, "redo"),
+        (Action::Hint, "hint"),
+    ];
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Action::CursorTo(x, y) = self {
+            return write!(f, "cursor-to {} {}", x, y);
+        }
+
+        let (_, name) = Self::NAMES.iter().find(|(a, _)| a == self).unwrap();
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(position) = s.strip_prefix("cursor-to ") {
+            return match position.split_once(' ') {
+                Some((x, y)) => match (x.parse(), y.parse()) {
+                    (Ok(x), Ok(y)) => Ok(Action::CursorTo(x, y)),
+                    _ => Err(format!("invalid position '{}'", position)),
+                },
+                None => Err(format!("invalid position '{}'", position)),
+            };
+        }
+
+        Self::NAMES
+            .iter()
+            .find(|(_, name)| *name == s)
+            .map(|(action, _)| *action)
+            .ok_or_else(|| format!("invalid action '{}'", s))
+    }
 }
 
 impl Field {
-    pub fn apply_action(&mut self, action: Action) {
+    /// Undoes the last flag or reveal and moves the cursor to the cell it was applied to.
+    ///
+    /// Once the game is over, nothing can be undone, except for the losing reveal in practice
+    /// mode.
+    ///
+    /// Returns `true` if a change was undone.
+    pub fn undo(&mut self) -> bool {
+        self.undo_change().is_some()
+    }
+
+    /// Undoes the last flag or reveal, see `Field::undo`, and returns it.
+    fn undo_change(&mut self) -> Option<Change> {
+        match self.game_state() {
+            GameState::Won => return None,
+            GameState::GameOver if !self.practice => return None,
+            _ => {}
+        }
+
+        let change = self.history.undo()?;
+        match &change {
+            Change::Flag((x, y)) => self.flag(*x, *y),
+            Change::Reveal { revealed, lost, .. } => {
+                for (x, y) in revealed {
+                    self.cells[y * self.width + x].hide()
+                }
+                if *lost {
+                    self.game_over = false
+                }
+            }
+        }
+        self.cursor_pos = change.position();
+        self.update_clock();
+
+        Some(change)
+    }
+
+    /// Redoes the last undone flag or reveal and moves the cursor to the cell it was applied to.
+    ///
+    /// Returns `true` if a change was redone.
+    pub fn redo(&mut self) -> bool {
+        self.redo_change().is_some()
+    }
+
+    /// Redoes the last undone flag or reveal, see `Field::redo`, and returns it.
+    fn redo_change(&mut self) -> Option<Change> {
+        let change = self.history.redo()?;
+        match &change {
+            Change::Flag((x, y)) => self.flag(*x, *y),
+            Change::Reveal { revealed, lost, .. } => {
+                for (x, y) in revealed {
+                    self.cells[y * self.width + x].reveal()
+                }
+                if *lost {
+                    self.game_over = true
+                }
+            }
+        }
+        self.cursor_pos = change.position();
+        self.update_clock();
+
+        Some(change)
+    }
+
+    /// Returns the cell highlighted by the last hint, if the hint found a mine.
+    pub fn highlight(&self) -> Option<(usize, usize)> {
+        self.highlight
+    }
+
+    /// Gives a hint, using the logical solver on what is visible of the field.
+    ///
+    /// If a cell is certainly safe, the cursor is moved to the closest such cell. Otherwise, if a
+    /// hidden cell is certainly a mine, the closest one is highlighted. If the solver cannot prove
+    /// anything, nothing happens.
+    ///
+    /// Returns `true` if a hint was given.
+    pub fn hint(&mut self) -> bool {
+        let deductions = solver::solve(self);
+
+        let (x, y) = self.cursor_pos;
+        let distance = |&(cx, cy): &(usize, usize)| cx.abs_diff(x).max(cy.abs_diff(y));
+        if let Some(safe) = deductions.safe.iter().min_by_key(|p| distance(p)) {
+            self.cursor_pos = *safe;
+            return true;
+        }
+        if let Some(mine) = deductions.mines.iter().min_by_key(|p| distance(p)) {
+            self.highlight = Some(*mine);
+            return true;
+        }
+
+        false
+    }
+
+    /// Records the cells revealed by a reveal at the given position in the history.
+    fn record_reveal(&mut self, at: (usize, usize), revealed: Vec<(usize, usize)>, was_over: bool) {
+        if revealed.is_empty() {
+            return;
+        }
+
+        let lost = self.game_over && !was_over;
+        self.history.record(Change::Reveal { at, revealed, lost })
+    }
+
+    /// Returns the event of toggling the flag of the cell at the position.
+    fn flag_event(&self, (x, y): (usize, usize)) -> Event {
+        if self.cells[y * self.width + x].is_flagged() {
+            Event::CellFlagged(x, y)
+        } else {
+            Event::CellUnflagged(x, y)
+        }
+    }
+
+    /// Returns the events of revealing the cells: the cells themselves, the mines among them, and
+    /// winning the game if it was won by them.
+    fn reveal_events(&self, revealed: &[(usize, usize)]) -> Vec<Event> {
+        let mut events: Vec<Event> = revealed
+            .iter()
+            .map(|&(x, y)| Event::CellRevealed(x, y))
+            .collect();
+        events.extend(
+            revealed
+                .iter()
+                .filter(|&&(x, y)| self.cells[y * self.width + x].is_mine())
+                .map(|&(x, y)| Event::MineHit(x, y)),
+        );
+        if !revealed.is_empty() && !self.game_over && self.has_won() {
+            events.push(Event::GameWon);
+        }
+        events
+    }
+
+    /// Applies the action to the field. Flags and reveals are recorded in the history, such that
+    /// they can be undone with `Action::Undo`.
+    ///
+    /// Once the game is won or lost, flags and reveals are no longer applied, and neither are
+    /// undo and redo, except for undoing the losing reveal in practice mode.
+    ///
+    /// Returns the events of what changed on the field, in the order it changed. An action that
+    /// changes nothing, like moving the cursor into the edge of the field, returns none.
+    pub fn apply_action(&mut self, action: Action) -> Vec<Event> {
+        let mut events = Vec::new();
+        let cursor = self.cursor_pos;
+
+        let playing = matches!(
+            action,
+            Action::Flag | Action::Reveal | Action::RevealAround | Action::Redo
+        );
+        if playing && self.game_state() != GameState::Running {
+            return events;
+        }
+
+        // A highlighted mine is only shown until the next action.
+        if self.highlight.take().is_some() {
+            events.push(Event::HighlightCleared);
+        }
+
         match action {
             Action::CursorUp => self.translate_y(-1),
             Action::CursorDown => self.translate_y(1),
@@ -376,23 +1106,95 @@ impl Field {
             Action::CursorToEdgeDown => self.move_cursor_to_edge(Edge::Down),
             Action::CursorToEdgeLeft => self.move_cursor_to_edge(Edge::Left),
             Action::CursorToEdgeRight => self.move_cursor_to_edge(Edge::Right),
+            Action::CursorTo(x, y) => self.move_cursor_to(x, y),
 
-            Action::Flag => self.flag(self.cursor_pos_x(), self.cursor_pos_y()),
+            Action::Flag => {
+                self.clicks.right += 1;
+                self.flag(self.cursor_pos_x(), self.cursor_pos_y());
+                self.history.record(Change::Flag(self.cursor_pos));
+                events.push(self.flag_event(self.cursor_pos));
+            }
             Action::Reveal => {
-                self.reveal(self.cursor_pos_x(), self.cursor_pos_y());
+                self.clicks.left += 1;
+                let was_over = self.game_over;
+                let revealed = self.reveal(self.cursor_pos_x(), self.cursor_pos_y());
+                events.extend(self.reveal_events(&revealed));
+                self.record_reveal(self.cursor_pos, revealed, was_over);
+            }
+            Action::RevealAround => {
+                self.clicks.chord += 1;
+                let was_over = self.game_over;
+                let revealed = self.reveal_from_cell(self.cursor_pos_x(), self.cursor_pos_y());
+                events.extend(self.reveal_events(&revealed));
+                self.record_reveal(self.cursor_pos, revealed, was_over);
+            }
+
+            Action::Undo => match self.undo_change() {
+                Some(Change::Flag(at)) => events.push(self.flag_event(at)),
+                Some(Change::Reveal { revealed, .. }) => {
+                    events.extend(revealed.iter().map(|&(x, y)| Event::CellHidden(x, y)))
+                }
+                None => {}
+            },
+            Action::Redo => match self.redo_change() {
+                Some(Change::Flag(at)) => events.push(self.flag_event(at)),
+                Some(Change::Reveal { revealed, .. }) => {
+                    events.extend(self.reveal_events(&revealed))
+                }
+                None => {}
+            },
+
+            Action::Hint => {
+                self.hint();
+                if let Some((x, y)) = self.highlight {
+                    events.push(Event::MineHighlighted(x, y));
+                }
             }
-            Action::RevealAround => self.reveal_from_cell(self.cursor_pos_x(), self.cursor_pos_y()),
         }
+
+        if self.cursor_pos != cursor {
+            events.push(Event::CursorMoved(self.cursor_pos.0, self.cursor_pos.1));
+        }
+        events
     }
 }
 
+/// The state of a game, as derived from its field.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum GameState {
+    /// The game is still being played.
     Running,
+    /// A mine has been revealed.
     GameOver,
+    /// Every cell without a mine has been revealed.
     Won,
 }
 
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Running => write!(f, "running"),
+            Self::GameOver => write!(f, "game-over"),
+            Self::Won => write!(f, "won"),
+        }
+    }
+}
+
+impl FromStr for GameState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "running" => Ok(Self::Running),
+            "game-over" => Ok(Self::GameOver),
+            "won" => Ok(Self::Won),
+            _ => Err(format!("invalid game state '{}'", s)),
+        }
+    }
+}
+
 impl Field {
+    /// Returns the state of the game.
     pub fn game_state(&self) -> GameState {
         if self.is_game_over() {
             GameState::GameOver
@@ -404,46 +1206,200 @@ impl Field {
     }
 }
 
-impl fmt::Display for Field {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        /***
-         * The logic, as seen in the original Swift file:
-         *
-         * (note: 'to place' is used as 'store representation as')
-         *
-         * if cell is in revealed state:
-         *     if mine:
-         *         place a bold, red 'M'
-         *     else:
-         *         place neighbours with color of neighbours*30 (over u8 range)
-         *     if flagged:
-         *         invert the placed cell
-         * else if flagged:
-         *     place an inverted, bold, italic, red 'F'
-         * else:
-         *     place '.'
-         *
-         * if cursor is on the cell, give the cell a yellow background color
-         ***/
-
-        let mut rows: Vec<String> = Vec::new();
-        for y in 0..self.height {
-            let mut row: Vec<String> = Vec::new();
-            for x in 0..self.width {
-                let cell = self.get_cell(x, y);
-
-                let cell_repr = if self.cursor_pos == (x, y) {
-                    // if cursor is on the cell, give the cell a yellow background color
-                    cell.apply_cursor_styling()
-                } else {
-                    format!("{}", cell)
-                };
-
-                row.push(cell_repr);
-            }
-            rows.push(row.concat());
+/// Fields shared by the tests of the crate.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+
+    /// Returns a field of the rows, see `Field::from_rows`, with the cells at `revealed` revealed.
+    pub(crate) fn field(rows: &[&str], revealed: &[(usize, usize)]) -> Field {
+        let mut f = Field::from_rows(rows).unwrap();
+        for &(x, y) in revealed {
+            f.reveal(x, y);
+        }
+        f
+    }
+
+    /// A 3 by 3 field with a single mine in the bottom right corner.
+    pub(crate) fn corner_mine() -> Field {
+        field(&["...", "...", "..*"], &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::{corner_mine, field};
+    use super::*;
+
+    #[test]
+    fn seed_gives_a_known_layout() {
+        let mut f = Field::with_seed(9, 9, 10, SafeStart::Opening, 4242).unwrap();
+        f.apply_action(Action::Reveal);
+
+        let mines: Vec<usize> = f
+            .mine_layout()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, &mine)| mine)
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(mines, [2, 4, 15, 36, 47, 49, 64, 69, 70, 73]);
+    }
+
+    #[test]
+    fn opening_reveals_every_cell_and_wins() {
+        let mut f = corner_mine();
+        let mut events = f.apply_action(Action::Reveal);
+        assert_eq!(events.pop(), Some(Event::GameWon));
+        events.sort_by_key(|event| match event {
+            Event::CellRevealed(x, y) => (*y, *x),
+            _ => panic!("unexpected event {:?}", event),
+        });
+        let cells = (0..3).flat_map(|y| (0..3).map(move |x| Event::CellRevealed(x, y)));
+        assert_eq!(events, cells.take(8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn events_of_single_actions() {
+        let mut f = corner_mine();
+        for (action, events) in [
+            (Action::CursorLeft, vec![]),
+            (Action::CursorTo(1, 1), vec![Event::CursorMoved(1, 1)]),
+            (Action::Reveal, vec![Event::CellRevealed(1, 1)]),
+            (Action::Undo, vec![Event::CellHidden(1, 1)]),
+            (Action::Redo, vec![Event::CellRevealed(1, 1)]),
+            // The 1 has no flag around it yet.
+            (Action::RevealAround, vec![]),
+            (Action::CursorToEdgeRight, vec![Event::CursorMoved(2, 1)]),
+            (Action::Flag, vec![Event::CellFlagged(2, 1)]),
+            (Action::Flag, vec![Event::CellUnflagged(2, 1)]),
+            (Action::Undo, vec![Event::CellFlagged(2, 1)]),
+            (Action::Redo, vec![Event::CellUnflagged(2, 1)]),
+            (Action::CursorDown, vec![Event::CursorMoved(2, 2)]),
+            (
+                Action::Reveal,
+                vec![Event::CellRevealed(2, 2), Event::MineHit(2, 2)],
+            ),
+        ] {
+            assert_eq!(f.apply_action(action), events, "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn chord_around_a_flag_wins() {
+        let mut f = corner_mine();
+        for action in [
+            Action::CursorTo(1, 1),
+            Action::Reveal,
+            Action::CursorTo(2, 2),
+        ] {
+            f.apply_action(action);
+        }
+        assert_eq!(f.apply_action(Action::Flag), [Event::CellFlagged(2, 2)]);
+        f.apply_action(Action::CursorTo(1, 1));
+
+        let events = f.apply_action(Action::RevealAround);
+        assert_eq!(events.len(), 8);
+        assert_eq!(events.last(), Some(&Event::GameWon));
+        assert!(!events.contains(&Event::CellRevealed(2, 2)));
+    }
+
+    #[test]
+    fn hint_highlights_until_the_next_action() {
+        let mut f = field(&["***", "*.*", "***"], &[(1, 1)]);
+        f.apply_action(Action::CursorTo(1, 1));
+
+        // Every hidden cell is a mine, the closest one is highlighted.
+        assert_eq!(f.apply_action(Action::Hint), [Event::MineHighlighted(0, 0)]);
+        assert_eq!(
+            f.apply_action(Action::CursorUp),
+            [Event::HighlightCleared, Event::CursorMoved(1, 0)]
+        );
+    }
+
+    #[test]
+    fn flood_reveal_clears_a_large_empty_field() {
+        let (width, height) = (500, 400);
+        let mut f = Field::with_seed(height, width, 0, SafeStart::Off, 7).unwrap();
+
+        let revealed = f.flood_reveal(&[height / 2 * width + width / 2]);
+        assert_eq!(revealed.len(), width * height);
+        assert!(f.cells.iter().all(|cell| cell.is_revealed()));
+        assert_eq!(f.game_state(), GameState::Won);
+    }
+
+    #[test]
+    fn no_guess_layout_can_be_solved() {
+        let mut f = Field::with_seed(9, 9, 10, SafeStart::Opening, 4242).unwrap();
+        f.set_no_guess(true);
+        f.apply_action(Action::Reveal);
+
+        // The first reveal is at the cursor, in the top left corner.
+        assert_eq!(f.no_guess(), NoGuess::On);
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert!(f.is_solvable_from(0, deadline));
+    }
+
+    #[test]
+    fn no_guess_falls_back_to_the_random_layout() {
+        // Too many mines to look for a layout that can be solved without guessing.
+        let layout = |no_guess| {
+            let mut f = Field::with_seed(9, 9, 30, SafeStart::Opening, 4242).unwrap();
+            f.set_no_guess(no_guess);
+            f.apply_action(Action::Reveal);
+            (f.no_guess(), f.mine_layout())
+        };
+
+        let (no_guess, fell_back) = layout(true);
+        assert_eq!(no_guess, NoGuess::FellBack);
+        assert_eq!(fell_back, layout(false).1);
+    }
+
+    #[test]
+    fn solvable_without_guessing_from_the_start() {
+        let f = field(&["*.*", "...", "..."], &[]);
+        let deadline = Instant::now() + Duration::from_secs(60);
+        // The opening at the bottom shows a 1-2-1, which leaves the middle of the top row safe.
+        assert!(f.is_solvable_from(7, deadline));
+        // The 1 at the left of the middle row opens nothing, and leaves nothing to deduce.
+        assert!(!f.is_solvable_from(3, deadline));
+        // The 1-2-1 needs the solver, which gives up once the deadline has passed.
+        assert!(!f.is_solvable_from(7, Instant::now()));
+    }
+
+    #[test]
+    fn won_game_is_not_played_further() {
+        let mut f = corner_mine();
+        f.apply_action(Action::Reveal);
+        assert_eq!(f.game_state(), GameState::Won);
+        f.reveal_all();
+
+        for action in [
+            Action::Undo,
+            Action::Flag,
+            Action::Reveal,
+            Action::RevealAround,
+        ] {
+            assert_eq!(f.apply_action(action), Vec::new());
         }
+        assert_eq!(f.game_state(), GameState::Won);
+    }
+
+    #[test]
+    fn losing_reveal_is_only_undone_in_practice() {
+        let mut f = corner_mine();
+        f.apply_action(Action::CursorTo(2, 2));
+        f.apply_action(Action::Reveal);
+        assert_eq!(f.game_state(), GameState::GameOver);
+        assert_eq!(f.apply_action(Action::Undo), Vec::new());
+        assert_eq!(f.apply_action(Action::Flag), Vec::new());
 
-        write!(f, "{}", rows.join("\n"))
+        let mut f = corner_mine();
+        f.set_practice(true);
+        f.apply_action(Action::CursorTo(2, 2));
+        f.apply_action(Action::Reveal);
+        assert_eq!(f.apply_action(Action::Undo), vec![Event::CellHidden(2, 2)]);
+        assert_eq!(f.game_state(), GameState::Running);
     }
 }
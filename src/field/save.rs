@@ -0,0 +1,372 @@
+//! Saving and loading of fields.
+//!
+//! A field is saved in a small, line based text format. The first line names the format and its
+//! version. It is followed by one `key value` line per property, and finally by the cells, one line
+//! per row:
+//!
+//! ```text
+//! mine save 3
+//! width 9
+//! height 9
+//! mines 10
+//! seed 4242
+//! safe_start opening
+//! mines_placed true
+//! no_guess off
+//! cursor 4 2
+//! game_over false
+//! practice false
+//! elapsed_ms 10523
+//! clicks 12 3 1
+//! cells
+//! 000000010
+//! ...
+//! ```
+//!
+//! Every cell is written as a single digit holding its state as bits: `1` if it is a mine, `2` if
+//! it has been revealed and `4` if it has been flagged. The neighbour counts are derived from the
+//! mines when loading. The undo history is not saved.
+//!
+//! When the format changes, `VERSION` is incremented and loading older versions is kept working.
+//! Version 1 has no `no_guess` line, and versions 1 and 2 have no `clicks` line.
+
+use super::{Field, FieldError, GameState, NoGuess, SafeStart};
+use crate::cell::Cell;
+use crate::clock::Clock;
+use crate::format::{self, invalid, parse};
+use crate::history::History;
+use crate::paths;
+use crate::stats::{Clicks, Regions};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+const MAGIC: &str = "mine save";
+const VERSION: u32 = 3;
+
+const MINE: u8 = 1;
+const REVEALED: u8 = 2;
+const FLAGGED: u8 = 4;
+
+impl Field {
+    /// Writes the field to the writer in the save format.
+    pub fn save(&self, mut w: impl Write) -> io::Result<()> {
+        format::write_header(&mut w, MAGIC, VERSION)?;
+        writeln!(w, "width {}", self.width)?;
+        writeln!(w, "height {}", self.height)?;
+        writeln!(w, "mines {}", self.mines)?;
+        writeln!(w, "seed {}", self.seed)?;
+        writeln!(w, "safe_start {}", self.safe_start)?;
+        writeln!(w, "mines_placed {}", self.mines_placed)?;
+        writeln!(w, "no_guess {}", self.no_guess)?;
+        writeln!(w, "cursor {} {}", self.cursor_pos.0, self.cursor_pos.1)?;
+        writeln!(w, "game_over {}", self.game_over)?;
+        writeln!(w, "practice {}", self.practice)?;
+        writeln!(w, "elapsed_ms {}", self.elapsed().as_millis())?;
+        writeln!(
+            w,
+            "clicks {} {} {}",
+            self.clicks.left, self.clicks.right, self.clicks.chord
+        )?;
+
+        writeln!(w, "cells")?;
+        for row in self.cells.chunks(self.width) {
+            let line: String = row
+                .iter()
+                .map(|cell| {
+                    let mut bits = 0;
+                    if cell.is_mine() {
+                        bits |= MINE
+                    }
+                    if cell.is_revealed() {
+                        bits |= REVEALED
+                    }
+                    if cell.is_flagged() {
+                        bits |= FLAGGED
+                    }
+                    (b'0' + bits) as char
+                })
+                .collect();
+            writeln!(w, "{}", line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Saves the field to the file at `path`, creating its directory if needed.
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = File::create(path)?;
+        self.save(&mut file)?;
+        file.flush()
+    }
+
+    /// Reads a field in the save format from the reader.
+    ///
+    /// If the game was still in progress, its clock is started again right away.
+    pub fn load(r: impl Read) -> io::Result<Self> {
+        let mut lines = BufReader::new(r).lines();
+        let mut next_line = || -> io::Result<String> {
+            lines
+                .next()
+                .unwrap_or_else(|| Err(invalid("unexpected end of save file".to_string())))
+        };
+
+        let header = next_line()?;
+        let version = format::read_header(&header, MAGIC, VERSION)?;
+
+        let mut value = |key: &str| -> io::Result<String> {
+            let line = next_line()?;
+            match line.split_once(' ') {
+                Some((k, v)) if k == key => Ok(v.to_string()),
+                _ => Err(invalid(format!("expected '{}', found '{}'", key, line))),
+            }
+        };
+
+        let width: usize = parse(&value("width")?)?;
+        let height: usize = parse(&value("height")?)?;
+        let mines = parse(&value("mines")?)?;
+        let seed = parse(&value("seed")?)?;
+        let safe_start: SafeStart = parse(&value("safe_start")?)?;
+        let mines_placed = parse(&value("mines_placed")?)?;
+        let no_guess = match version {
+            1 => NoGuess::Off,
+            _ => parse(&value("no_guess")?)?,
+        };
+        let cursor = value("cursor")?;
+        let cursor_pos = match cursor.split_once(' ') {
+            Some((x, y)) => (parse(x)?, parse(y)?),
+            None => return Err(invalid(format!("invalid cursor '{}'", cursor))),
+        };
+        let game_over = parse(&value("game_over")?)?;
+        let practice = parse(&value("practice")?)?;
+        let elapsed = Duration::from_millis(parse(&value("elapsed_ms")?)?);
+        let clicks = match version {
+            1 | 2 => Clicks::default(),
+            _ => {
+                let clicks = value("clicks")?;
+                match clicks.split(' ').collect::<Vec<_>>()[..] {
+                    [left, right, chord] => Clicks {
+                        left: parse(left)?,
+                        right: parse(right)?,
+                        chord: parse(chord)?,
+                    },
+                    _ => return Err(invalid(format!("invalid clicks '{}'", clicks))),
+                }
+            }
+        };
+
+        let cells = format::field_cells(width, height)?;
+        if mines > cells {
+            return Err(invalid(
+                FieldError::TooManyMines { mines, cells }.to_string(),
+            ));
+        }
+        if cursor_pos.0 >= width || cursor_pos.1 >= height {
+            return Err(invalid("cursor is outside of the field".to_string()));
+        }
+
+        if next_line()? != "cells" {
+            return Err(invalid("expected 'cells'".to_string()));
+        }
+        // The size is not trusted to allocate for, the cells are only kept once they are read.
+        let mut cells = Vec::new();
+        for _ in 0..height {
+            let line = next_line()?;
+            if line.len() != width {
+                return Err(invalid(format!("expected a row of {} cells", width)));
+            }
+            for c in line.bytes() {
+                let bits = match c {
+                    b'0'..=b'7' => c - b'0',
+                    _ => return Err(invalid(format!("invalid cell '{}'", c as char))),
+                };
+                let mut cell = Cell::new();
+                cell.mine = bits & MINE != 0;
+                if bits & REVEALED != 0 {
+                    cell.reveal()
+                }
+                if bits & FLAGGED != 0 {
+                    cell.toggle_flagged()
+                }
+                cells.push(cell);
+            }
+        }
+
+        let placed = cells.iter().filter(|cell| cell.is_mine()).count();
+        if mines_placed && placed != mines {
+            return Err(invalid(format!(
+                "expected {} mines, found {}",
+                mines, placed
+            )));
+        }
+        // Before the mines are placed, the first reveal places them, so no cell can be either.
+        if !mines_placed
+            && cells
+                .iter()
+                .any(|cell| cell.is_mine() || cell.is_revealed())
+        {
+            return Err(invalid(
+                "cells are mines or revealed before the mines are placed".to_string(),
+            ));
+        }
+
+        let mut f = Self {
+            height,
+            width,
+            mines,
+            seed,
+            game_over,
+            cells,
+            cursor_pos,
+            safe_start,
+            mines_placed,
+            no_guess,
+            history: History::new(),
+            practice,
+            clock: Clock::with_elapsed(elapsed),
+            clicks,
+            highlight: None,
+            three_bv: Regions::default(),
+        };
+        f.initialize_neighbours();
+        if mines_placed {
+            f.three_bv = Regions::of(&f);
+        }
+        f.update_clock();
+
+        Ok(f)
+    }
+
+    /// Loads a field from the save file at `path`.
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        Self::load(File::open(path)?)
+    }
+
+    /// Loads the game that was autosaved when quitting. See `Field::autosave`.
+    pub fn resume() -> io::Result<Self> {
+        let path = autosave_path()?;
+        match Self::load_from(&path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "there is no saved game to resume",
+            )),
+            result => result,
+        }
+    }
+
+    /// Keeps the autosave up to date with the field.
+    ///
+    /// A game in progress is saved, such that it can be continued with `Field::resume`. Once the
+    /// game is over or won, the autosave is removed. A game in which nothing has been revealed yet
+    /// leaves the autosave alone.
+    pub fn autosave(&self) -> io::Result<()> {
+        let path = autosave_path()?;
+        match self.game_state() {
+            GameState::Running if self.mines_placed => self.save_to(&path),
+            GameState::Running => Ok(()),
+            GameState::GameOver | GameState::Won => match fs::remove_file(&path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+fn autosave_path() -> io::Result<std::path::PathBuf> {
+    paths::autosave().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "could not determine the data directory for the autosave",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Action;
+
+    fn save(f: &Field) -> String {
+        let mut out = Vec::new();
+        f.save(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Returns the save without the time, which differs between two saves of a game in progress.
+    fn without_time(save: &str) -> Vec<&str> {
+        save.lines()
+            .filter(|line| !line.starts_with("elapsed_ms"))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_keeps_the_game() {
+        let mut f = Field::with_seed(9, 9, 10, SafeStart::Opening, 4242).unwrap();
+        f.set_no_guess(true);
+        for action in [
+            Action::Reveal,
+            Action::CursorTo(0, 4),
+            Action::Flag,
+            Action::CursorRight,
+        ] {
+            f.apply_action(action);
+        }
+
+        let saved = save(&f);
+        let loaded = Field::load(saved.as_bytes()).unwrap();
+        assert_eq!(without_time(&save(&loaded)), without_time(&saved));
+        assert!(loaded.cells() == f.cells());
+        assert_eq!(loaded.clicks(), f.clicks());
+        assert_eq!(loaded.no_guess(), f.no_guess());
+        assert_eq!(loaded.game_state(), GameState::Running);
+    }
+
+    #[test]
+    fn version_1_has_no_guess_off_and_no_clicks() {
+        let save = "mine save 1\nwidth 2\nheight 1\nmines 1\nseed 7\nsafe_start cell\n\
+                    mines_placed true\ncursor 1 0\ngame_over false\npractice false\n\
+                    elapsed_ms 1500\ncells\n12\n";
+
+        let f = Field::load(save.as_bytes()).unwrap();
+        assert_eq!(f.no_guess(), NoGuess::Off);
+        assert_eq!(f.clicks(), Clicks::default());
+        assert!(f.get_cell(0, 0).is_mine());
+        assert!(f.get_cell(1, 0).is_revealed());
+        assert_eq!(f.get_cell(1, 0).neighbours(), 1);
+    }
+
+    #[test]
+    fn invalid_saves_are_rejected() {
+        let huge = "mine save 3\nwidth 4294967296\nheight 4294967296\nmines 1\nseed 7\n\
+                    safe_start cell\nmines_placed false\nno_guess off\ncursor 0 0\n\
+                    game_over false\npractice false\nelapsed_ms 0\nclicks 0 0 0\ncells\n";
+        for save in ["mine save 4\n", "mine replay 1\n", huge] {
+            let err = Field::load(save.as_bytes()).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn cells_before_the_mines_are_placed_are_hidden_and_safe() {
+        let save = |cells: &str| {
+            format!(
+                "mine save 3\nwidth 2\nheight 1\nmines 1\nseed 7\nsafe_start cell\n\
+                 mines_placed false\nno_guess off\ncursor 0 0\ngame_over false\n\
+                 practice false\nelapsed_ms 0\nclicks 0 0 0\ncells\n{}\n",
+                cells
+            )
+        };
+
+        // Flags can be placed before the first reveal.
+        assert!(Field::load(save("04").as_bytes()).is_ok());
+        for cells in ["10", "02", "30"] {
+            let err = Field::load(save(cells).as_bytes()).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
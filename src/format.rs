@@ -0,0 +1,45 @@
+//! Helpers shared by the line based file formats of saves, replays and records.
+//!
+//! Every file starts with a header line of a magic string, which names the format, and the version
+//! of the format, like `mine save 3`. Values are written with `Display` and read with `FromStr`.
+
+use crate::field;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Writes the header line of the format.
+pub fn write_header(mut w: impl Write, magic: &str, version: u32) -> io::Result<()> {
+    writeln!(w, "{} {}", magic, version)
+}
+
+/// Returns the version in the header line of the format, which is at most `latest`.
+pub fn read_header(header: &str, magic: &str, latest: u32) -> io::Result<u32> {
+    let version = header
+        .strip_prefix(magic)
+        .and_then(|version| version.trim().parse::<u32>().ok())
+        .ok_or_else(|| invalid(format!("not a {} file", magic)))?;
+    if version == 0 || version > latest {
+        return Err(invalid(format!(
+            "unsupported {} file version {} (expected at most {})",
+            magic, version, latest
+        )));
+    }
+    Ok(version)
+}
+
+/// Returns the number of cells of a field of the size, unless there are none or too many to count.
+pub fn field_cells(width: usize, height: usize) -> io::Result<usize> {
+    field::cell_count(width, height).map_err(|err| invalid(err.to_string()))
+}
+
+/// Parses a value of a file.
+pub fn parse<T: FromStr>(value: &str) -> io::Result<T> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("invalid value '{}'", value)))
+}
+
+/// Returns the error of a file that is not valid.
+pub fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+
+/// The maximum number of changes kept in the history.
+const MAX_CHANGES: usize = 512;
+
+/// The maximum number of cells stored over all changes in the history. A single reveal on a large,
+/// sparse field can reveal millions of cells, so the number of changes alone does not keep the
+/// history small.
+const MAX_CELLS: usize = 1 << 16;
+
+/// A single state-changing action, as it was applied to the field.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Change {
+    /// The flag of the cell at the position was toggled.
+    Flag((usize, usize)),
+    /// The cells were revealed by revealing (around) the cell at the position.
+    Reveal {
+        at: (usize, usize),
+        revealed: Vec<(usize, usize)>,
+        /// Whether a mine was revealed, ending the game.
+        lost: bool,
+    },
+}
+
+impl Change {
+    /// Returns the position of the cell the action was applied to.
+    pub fn position(&self) -> (usize, usize) {
+        match self {
+            Change::Flag(at) => *at,
+            Change::Reveal { at, .. } => *at,
+        }
+    }
+
+    /// Returns the number of cells stored in the change.
+    fn size(&self) -> usize {
+        match self {
+            Change::Flag(_) => 1,
+            Change::Reveal { revealed, .. } => revealed.len(),
+        }
+    }
+}
+
+/// The undo/redo history of the changes made to a field.
+///
+/// The history is capped by `MAX_CHANGES` and `MAX_CELLS`. When it grows too large, the oldest
+/// changes are forgotten. A change that is larger than `MAX_CELLS` on its own cannot be undone.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct History {
+    undo: VecDeque<Change>,
+    redo: Vec<Change>,
+    cells: usize,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a new change. This clears the changes that could be redone.
+    pub fn record(&mut self, change: Change) {
+        self.redo.clear();
+        self.push_undo(change);
+    }
+
+    /// Takes the last change off the history, such that it can be undone. It can be redone
+    /// afterwards.
+    pub fn undo(&mut self) -> Option<Change> {
+        let change = self.undo.pop_back()?;
+        self.cells -= change.size();
+        self.redo.push(change.clone());
+        Some(change)
+    }
+
+    /// Takes the last undone change, such that it can be redone.
+    pub fn redo(&mut self) -> Option<Change> {
+        let change = self.redo.pop()?;
+        self.push_undo(change.clone());
+        Some(change)
+    }
+
+    fn push_undo(&mut self, change: Change) {
+        self.cells += change.size();
+        self.undo.push_back(change);
+
+        while self.undo.len() > MAX_CHANGES || self.cells > MAX_CELLS {
+            match self.undo.pop_front() {
+                Some(oldest) => self.cells -= oldest.size(),
+                None => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! The minesweeper engine behind Mine.
+//!
+//! A game is played on a [`Field`], by applying [`Action`]s to it until its [`GameState`] is no
+//! longer running. Every action returns the [`Event`]s of what it changed. The field takes care of
+//! placing the mines, revealing, flagging, undoing and hints, and can be saved, loaded and
+//! recorded to a replay. The [`solver`] and [`probability`] modules analyse what is visible of a
+//! field, like a player would.
+//!
+//! The engine has no frontend dependencies. The [`view`] module tells frontends what to draw for
+//! every cell, without deciding on how it looks.
+//!
+//! ```
+//! use mine::{Action, Event, Field, GameState};
+//! use mine::field::SafeStart;
+//!
+//! let mut field = Field::with_seed(9, 9, 10, SafeStart::Opening, 4242)?;
+//! let events = field.apply_action(Action::Reveal);
+//! assert_eq!(events.first(), Some(&Event::CellRevealed(0, 0)));
+//! assert_eq!(field.game_state(), GameState::Running);
+//! # Ok::<(), mine::field::FieldError>(())
+//! ```
+
+#![warn(missing_docs)]
+
+pub mod cell;
+mod clock;
+pub mod difficulty;
+pub mod field;
+mod format;
+mod history;
+pub mod paths;
+pub mod probability;
+pub mod records;
+pub mod replay;
+mod rng;
+pub mod solver;
+pub mod stats;
+pub mod view;
+
+pub use cell::{Cell, CellState};
+pub use difficulty::{Difficulty, Mines};
+pub use field::{Action, Event, Field, FieldError, GameState};
+pub use stats::Stats;
+pub use view::CellView;
@@ -1,46 +1,75 @@
 #[cfg(feature = "gui")]
 use crate::mode::gui::gui;
 #[cfg(feature = "tui")]
+use crate::mode::replay::replay;
+use crate::mode::stats::stats;
+#[cfg(feature = "tui")]
 use crate::mode::tui::tui;
 
+#[cfg(any(feature = "tui", feature = "gui"))]
+use config::GameConfig;
+#[cfg(feature = "tui")]
+use mode::theme::LookConfig;
+#[cfg(feature = "tui")]
+use std::path::PathBuf;
+use std::process;
 use structopt::StructOpt;
 
-mod cell;
-mod field;
+#[cfg(any(feature = "tui", feature = "gui"))]
+mod config;
 mod mode;
+#[cfg(any(feature = "tui", feature = "gui"))]
+mod session;
 
 /// Terminal interface for Mine
 #[cfg(feature = "tui")]
 #[derive(StructOpt)]
 pub struct Tui {
-    /// Field width.
-    #[structopt(short, long, default_value = "9")]
-    width: usize,
+    #[structopt(flatten)]
+    game: GameConfig,
 
-    /// Field height.
-    #[structopt(short, long, default_value = "9")]
-    height: usize,
-
-    /// The number of mines to be placed in the field.
-    #[structopt(short, long, default_value = "10")]
-    mines: usize,
+    #[structopt(flatten)]
+    look: LookConfig,
 }
 
 /// Graphical interface for Mine
 #[cfg(feature = "gui")]
 #[derive(StructOpt)]
 pub struct Gui {
-    /// Field width.
-    #[structopt(short, long, default_value = "9")]
-    width: u32,
+    #[structopt(flatten)]
+    game: GameConfig,
+}
+
+/// Play back a recorded game in the terminal
+#[cfg(feature = "tui")]
+#[derive(StructOpt)]
+pub struct Replay {
+    /// The replay file, as written by `--record`.
+    #[structopt(parse(from_os_str))]
+    file: PathBuf,
+
+    /// Playback speed, as a multiple of the recorded speed.
+    #[structopt(short, long, default_value = "1.0")]
+    speed: f64,
 
-    /// Field height.
-    #[structopt(short, long, default_value = "9")]
-    height: u32,
+    /// Only check whether the game ends in the recorded state, without playing it back.
+    #[structopt(long)]
+    verify: bool,
 
-    /// The number of mines to be placed in the field.
-    #[structopt(short, long, default_value = "10")]
-    mines: u32,
+    #[structopt(flatten)]
+    look: LookConfig,
+}
+
+/// Show the statistics and best times of finished games
+#[derive(StructOpt)]
+pub struct Stats {
+    /// The number of best times to show for every board.
+    #[structopt(short, long, default_value = "5")]
+    top: usize,
+
+    /// Write every finished game as comma separated values, instead of the statistics.
+    #[structopt(long)]
+    csv: bool,
 }
 
 /// Mine: a minesweeper game for the terminal and gui.
@@ -50,6 +79,9 @@ enum Command {
     Tui(Tui),
     #[cfg(feature = "gui")]
     Gui(Gui),
+    #[cfg(feature = "tui")]
+    Replay(Replay),
+    Stats(Stats),
 }
 
 #[derive(StructOpt)]
@@ -59,13 +91,21 @@ struct Opt {
     command: Command,
 }
 
-fn main() -> Result<(), std::io::Error> {
-    let opt = Opt::from_args();
+fn main() {
+    if let Err(err) = run(Opt::from_args().command) {
+        eprintln!("error: {}", err);
+        process::exit(1)
+    }
+}
 
-    match opt.command {
+fn run(command: Command) -> Result<(), std::io::Error> {
+    match command {
         #[cfg(feature = "tui")]
         Command::Tui(opt) => tui(opt),
         #[cfg(feature = "gui")]
         Command::Gui(opt) => gui(opt),
+        #[cfg(feature = "tui")]
+        Command::Replay(opt) => replay(opt),
+        Command::Stats(opt) => stats(opt),
     }
 }
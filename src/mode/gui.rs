@@ -1,22 +1,51 @@
+use super::hud::{self, Layout};
+use super::input::{Input, InputConfig, InputEvent};
+use super::keymap::{Command, KeyPress, Keymap};
+use super::mouse::{Button, MouseEvent};
 use crate::{
-    cell::CellState,
-    field::{Action, Edge, Field, GameState},
+    config::{game_with_size, new_game, no_guess_note, parse_size, same_game},
+    session::Session,
     Gui,
 };
+use mine::{
+    field::{Field, GameState},
+    probability::mine_probabilities,
+    replay::{apply, Recorder},
+    stats::Stats,
+};
 use pixels::{Pixels, SurfaceTexture};
+use std::io;
+use std::time::{Duration, Instant};
 use winit::{
-    dpi::{LogicalPosition, LogicalSize, PhysicalSize},
-    event::{ElementState, Event, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent},
+    dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize},
+    event::{
+        ElementState, Event, KeyboardInput, ModifiersState, MouseButton, VirtualKeyCode,
+        WindowEvent,
+    },
     event_loop::{ControlFlow, EventLoop},
+    window::Window,
 };
 
 pub fn gui(opt: Gui) -> Result<(), std::io::Error> {
-    // Initialize some sensible default values.
-    let width = opt.width;
-    let height = opt.height;
-    let mines = opt.mines;
-    let mut f = Field::new(height as usize, width as usize, mines as usize);
-    let mut old_field = f.clone();
+    let opt = opt.game;
+    let mut f = opt.field()?;
+    let mut layout = Layout::of(&f);
+    let width = layout.width as u32;
+    let height = layout.height as u32;
+
+    let mut message: Option<String> = None;
+    let mut recorder = opt.record.as_ref().map(|_| Recorder::new(&f));
+    let mut heatmap = false;
+    // The statistics of a game that has ended, from before the field was revealed.
+    let mut end_stats: Option<Stats> = None;
+    let mut session = Session::default();
+    // The settings typed so far at the prompt for a new game, while it is open.
+    let mut prompt: Option<String> = None;
+    // Whether the next character is that of the key that opened the prompt.
+    let mut skip_character = false;
+    let keymap = Keymap::load()?;
+    // The time shown by the timer, in seconds.
+    let mut shown_seconds = f.elapsed().as_secs();
 
     // Set up window.
     let event_loop = EventLoop::new();
@@ -30,12 +59,13 @@ pub fn gui(opt: Gui) -> Result<(), std::io::Error> {
     // TODO: This unwrap() must go.
     let mut pixels = Pixels::new(width, height, surface_texture).unwrap();
     //let mut paused = false;
-    let field_ratio = width as f64 / height as f64;
 
     //let mut draw_state: Option<bool> = None;
     //let mut redraw = false;
-    let mut previous_input: Option<VirtualKeyCode> = None;
     let mut modifiers = ModifiersState::empty();
+    let mut input = Input::new(InputConfig::load()?);
+    // The position of the mouse in the window, while it is inside of it.
+    let mut mouse_position: Option<PhysicalPosition<f64>> = None;
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Wait;
@@ -45,11 +75,25 @@ pub fn gui(opt: Gui) -> Result<(), std::io::Error> {
                 event: WindowEvent::CloseRequested,
                 window_id,
             } if window_id == window.id() => *control_flow = ControlFlow::Exit,
+            Event::LoopDestroyed => {
+                if let (Some(recorder), Some(path)) = (&recorder, &opt.record) {
+                    if let Err(err) = recorder.finish_to(&f, path) {
+                        eprintln!("could not write the replay: {}", err)
+                    }
+                }
+
+                // Save a game in progress, such that it can be resumed. A finished game removes
+                // the save.
+                if let Err(err) = f.autosave() {
+                    eprintln!("could not save the game: {}", err)
+                }
+            }
             Event::WindowEvent {
                 event: WindowEvent::Resized(resized_physical_size),
                 window_id,
             } if window_id == window.id() => {
                 pixels.resize_surface(resized_physical_size.width, resized_physical_size.height);
+                let field_ratio = layout.width as f64 / layout.height as f64;
                 let width_height_ratio =
                     resized_physical_size.width as f64 / resized_physical_size.height as f64;
                 if width_height_ratio > field_ratio {
@@ -75,6 +119,7 @@ pub fn gui(opt: Gui) -> Result<(), std::io::Error> {
                     WindowEvent::ModifiersChanged(modifiers_changed) => {
                         modifiers = modifiers_changed
                     }
+                    // While the prompt for a new game is open, keys edit the settings.
                     WindowEvent::KeyboardInput {
                         input:
                             KeyboardInput {
@@ -83,67 +128,162 @@ pub fn gui(opt: Gui) -> Result<(), std::io::Error> {
                                 ..
                             },
                         ..
-                    } => {
-                        match virtual_key_code {
-                            // Flagging and revealing
-                            VirtualKeyCode::F | VirtualKeyCode::Space => {
-                                f.apply_action(Action::Flag)
+                    } if prompt.is_some() => match virtual_key_code {
+                        VirtualKeyCode::Return => {
+                            let settings = prompt.take().unwrap_or_default();
+                            if !settings.trim().is_empty() {
+                                let new = parse_size(&settings)
+                                    .and_then(|size| game_with_size(&f, size));
+                                message = start(new, &mut f, &mut layout, &mut pixels, &window);
+                                input.interrupt();
                             }
-                            VirtualKeyCode::R | VirtualKeyCode::Return | VirtualKeyCode::Tab => {
-                                f.apply_action(Action::Reveal);
-
-                                // If the previous input and the current input are the same, when the cell
-                                // is attempted to be revealed, this is considered a double press. In that
-                                // case, the neighbouring cells are to be revealed too, when possible.
-                                if previous_input == Some(virtual_key_code) {
-                                    f.apply_action(Action::RevealAround)
+                        }
+                        VirtualKeyCode::Back => {
+                            prompt.as_mut().map(String::pop);
+                        }
+                        VirtualKeyCode::Escape => prompt = None,
+                        _ => {}
+                    },
+                    WindowEvent::ReceivedCharacter(_) if skip_character => {
+                        skip_character = false
+                    }
+                    WindowEvent::ReceivedCharacter(c) => match &mut prompt {
+                        Some(prompt) if !c.is_control() => prompt.push(c),
+                        _ => {}
+                    },
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(virtual_key_code),
+                                ..
+                            },
+                        ..
+                    } => {
+                        message = None;
+                        let press = KeyPress::from_winit(virtual_key_code, modifiers);
+                        match press.and_then(|press| keymap.command(&press)) {
+                            // Play the same seed again once the game has ended
+                            Some(Command::Restart) if f.game_state() != GameState::Running => {
+                                input.interrupt();
+                                message = match recorder {
+                                    Some(_) => Some(RECORDING.to_string()),
+                                    None => {
+                                        start(same_game(&f), &mut f, &mut layout, &mut pixels, &window)
+                                    }
                                 }
                             }
 
-                            // Quit the application
-                            VirtualKeyCode::Q => *control_flow = ControlFlow::Exit,
+                            // Toggle the heatmap of mine probabilities
+                            Some(Command::Heatmap) => {
+                                heatmap = !heatmap;
+                                window.request_redraw()
+                            }
 
-                            // Basic movement
-                            // Shift not held.
-                            code if !modifiers.shift() => match code {
-                                VirtualKeyCode::Up | VirtualKeyCode::K => {
-                                    f.apply_action(Action::CursorUp)
-                                }
-                                VirtualKeyCode::Down | VirtualKeyCode::J => {
-                                    f.apply_action(Action::CursorDown)
-                                }
-                                VirtualKeyCode::Left | VirtualKeyCode::H => {
-                                    f.apply_action(Action::CursorLeft)
-                                }
-                                VirtualKeyCode::Right | VirtualKeyCode::L => {
-                                    f.apply_action(Action::CursorRight)
-                                }
+                            // Save the game
+                            Some(Command::Save) => {
+                                let save_path = opt.save_path(&f);
+                                message = Some(match f.save_to(&save_path) {
+                                    Ok(()) => format!("Mine — saved to {}", save_path.display()),
+                                    Err(err) => format!("Mine — could not save: {}", err),
+                                })
+                            }
 
-                                // Edge movements Down and Left
-                                VirtualKeyCode::G => f.move_cursor_to_edge(Edge::Up),
-                                VirtualKeyCode::Key0 => f.move_cursor_to_edge(Edge::Left),
-                                _ => {}
-                            },
-                            // Shift held.
-                            code if modifiers.shift() => match code {
-                                // Edge movements Up, Down, Left, Right
-                                VirtualKeyCode::Up | VirtualKeyCode::K => {
-                                    f.apply_action(Action::CursorToEdgeUp)
+                            // A new game with the same settings
+                            Some(Command::NewGame) => {
+                                input.interrupt();
+                                message = match recorder {
+                                    Some(_) => Some(RECORDING.to_string()),
+                                    None => {
+                                        start(new_game(&f), &mut f, &mut layout, &mut pixels, &window)
+                                    }
                                 }
-                                VirtualKeyCode::Down | VirtualKeyCode::J | VirtualKeyCode::G => {
-                                    f.apply_action(Action::CursorToEdgeDown)
+                            }
+
+                            // A new game with new settings, which are typed at a prompt
+                            Some(Command::NewSettings) => {
+                                if recorder.is_some() {
+                                    message = Some(RECORDING.to_string());
+                                } else {
+                                    prompt = Some(String::new());
+                                    // The character of the key itself is not to end up in the
+                                    // prompt.
+                                    skip_character = press.is_some_and(|press| press.is_typed());
                                 }
-                                VirtualKeyCode::Left | VirtualKeyCode::H => {
-                                    f.apply_action(Action::CursorToEdgeLeft)
+                            }
+
+                            // Quit the application
+                            Some(Command::Quit) => *control_flow = ControlFlow::Exit,
+
+                            // Once the game has ended, the field is not played any further. Only
+                            // the losing reveal can be undone, in practice mode.
+                            Some(command)
+                                if f.game_state() != GameState::Running
+                                    && command != Command::Undo => {}
+
+                            // Movement, flagging, revealing, hints, undo and redo. The whole field
+                            // is shown, so there are no pages to scroll.
+                            Some(command) => {
+                                let event = InputEvent::Key(command);
+                                for action in input.actions(event, &f, Instant::now()) {
+                                    if !apply(&mut f, &mut recorder, action).is_empty() {
+                                        window.request_redraw()
+                                    }
                                 }
-                                VirtualKeyCode::Right
-                                | VirtualKeyCode::L
-                                | VirtualKeyCode::Key4 => f.apply_action(Action::CursorToEdgeRight),
-                                _ => {}
-                            },
-                            _ => {}
+                            }
+                            None => {}
+                        }
+                    }
+                    WindowEvent::CursorMoved { position, .. } => {
+                        mouse_position = Some(position);
+                        let cell = pixel_at(&pixels, position)
+                            .and_then(|pixel| layout.cell_at(pixel, &f))
+                            .filter(|_| f.game_state() == GameState::Running);
+                        let event = InputEvent::Mouse(MouseEvent::Move, cell);
+                        for action in input.actions(event, &f, Instant::now()) {
+                            if !apply(&mut f, &mut recorder, action).is_empty() {
+                                window.request_redraw()
+                            }
+                        }
+                    }
+                    WindowEvent::CursorLeft { .. } => mouse_position = None,
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        let button = match button {
+                            MouseButton::Left => Button::Left,
+                            MouseButton::Middle => Button::Middle,
+                            MouseButton::Right => Button::Right,
+                            MouseButton::Other(_) => return,
+                        };
+                        let event = match state {
+                            ElementState::Pressed => MouseEvent::Press(button),
+                            ElementState::Released => MouseEvent::Release(button),
+                        };
+                        let pixel = mouse_position.and_then(|position| pixel_at(&pixels, position));
+
+                        message = None;
+                        if event == MouseEvent::Press(Button::Left)
+                            && pixel.is_some_and(|pixel| layout.is_on_face(pixel))
+                        {
+                            // The face starts a new game.
+                            message = match recorder {
+                                Some(_) => Some(RECORDING.to_string()),
+                                None => start(new_game(&f), &mut f, &mut layout, &mut pixels, &window),
+                            };
+                            input.interrupt();
+                            return;
+                        }
+
+                        // Once the game has ended, the buttons are only kept track of, as if the
+                        // mouse were not on the field.
+                        let cell = pixel
+                            .and_then(|pixel| layout.cell_at(pixel, &f))
+                            .filter(|_| f.game_state() == GameState::Running);
+                        let event = InputEvent::Mouse(event, cell);
+                        for action in input.actions(event, &f, Instant::now()) {
+                            if !apply(&mut f, &mut recorder, action).is_empty() {
+                                window.request_redraw()
+                            }
                         }
-                        previous_input = Some(virtual_key_code)
                     }
                     _ => {}
                 }
@@ -151,23 +291,65 @@ pub fn gui(opt: Gui) -> Result<(), std::io::Error> {
             Event::MainEventsCleared => {
                 // Application update code.
 
-                match f.game_state() {
+                let title = match f.game_state() {
                     GameState::Won => {
-                        window.set_title("You won :)");
-                        f.reveal_all();
+                        // The statistics are taken before the whole field is revealed.
+                        let stats = finish(&f, &mut end_stats, &mut session);
+                        if !f.reveal_all().is_empty() {
+                            window.request_redraw()
+                        }
+                        format!("You won :) — {} — {} — {}", stats, session, end_keys(&keymap))
+                    }
+                    GameState::GameOver if f.is_practice() => {
+                        // Keep the rest of the mines hidden, such that the losing reveal can be
+                        // undone.
+                        let undo = match keymap.key(Command::Undo) {
+                            Some(key) => format!("press {} to undo — ", key),
+                            None => String::new(),
+                        };
+                        format!(
+                            "You lost :( — {}{} — {}",
+                            undo,
+                            Stats::of(&f),
+                            end_keys(&keymap)
+                        )
                     }
                     GameState::GameOver => {
-                        window.set_title("You lost :(");
-                        f.reveal_all();
+                        let stats = finish(&f, &mut end_stats, &mut session);
+                        if !f.reveal_all().is_empty() {
+                            window.request_redraw()
+                        }
+                        format!(
+                            "You lost :( — {} — {} — {}",
+                            stats,
+                            session,
+                            end_keys(&keymap)
+                        )
+                    }
+                    GameState::Running => {
+                        end_stats = None;
+                        format!(
+                            "Mine — {} out of {} mines left — {} — seed {}{}",
+                            f.mines_left(),
+                            f.total_mines(),
+                            Stats::of(&f),
+                            f.seed(),
+                            no_guess_note(&f)
+                        )
                     }
-                    GameState::Running => window.set_title(&format!(
-                        "Mine — {} out of {} mines left",
-                        f.mines_left(),
-                        f.total_mines()
+                };
+                match (&prompt, &message) {
+                    (Some(prompt), _) => window.set_title(&format!(
+                        "Mine — new game — beginner, intermediate, expert, or a size like 20x10 50: {}_",
+                        prompt
                     )),
+                    (None, Some(message)) => window.set_title(message),
+                    (None, None) => window.set_title(&title),
                 }
 
-                if old_field != f {
+                // Changes to the field have requested a redraw already, the timer is redrawn once
+                // a second.
+                if f.elapsed().as_secs() != shown_seconds {
                     // Queue a RedrawRequested event.
                     //
                     // You only need to call this if you've determined that you need to redraw, in
@@ -175,8 +357,6 @@ pub fn gui(opt: Gui) -> Result<(), std::io::Error> {
                     // can just render here instead.
                     window.request_redraw();
                 }
-
-                old_field = f.clone();
             }
             Event::RedrawRequested(_) => {
                 // Redraw the application.
@@ -185,21 +365,51 @@ pub fn gui(opt: Gui) -> Result<(), std::io::Error> {
                 // this event rather than in MainEventsCleared, since rendering in here allows
                 // the program to gracefully handle redraws requested by the OS.
 
-                draw(&f, pixels.get_frame());
+                let probabilities = if heatmap {
+                    mine_probabilities(&f)
+                } else {
+                    None
+                };
+                hud::draw(&f, probabilities.as_deref(), &layout, pixels.get_frame());
+                shown_seconds = f.elapsed().as_secs();
                 if pixels
                     .render()
                     .map_err(|e| format!("pixels.render() failed: {}", e))
                     .is_err()
                 {
                     *control_flow = ControlFlow::Exit;
-                    return;
                 }
             }
+            Event::RedrawEventsCleared if f.is_timing() => {
+                // Wake up when the timer is to show the next second.
+                let next_second = Duration::from_secs(1)
+                    - Duration::from_nanos(f.elapsed().subsec_nanos() as u64);
+                *control_flow = ControlFlow::WaitUntil(Instant::now() + next_second);
+            }
             _ => (),
         }
     });
 }
 
+/// The message shown when a new game is to be started while recording, as the replay is of a
+/// single game.
+const RECORDING: &str = "Mine — cannot start a new game while recording";
+
+/// Returns the keys that can be pressed once a game has ended, as listed in the title. Commands
+/// that are not bound to a key are left out.
+fn end_keys(keymap: &Keymap) -> String {
+    let options: Vec<String> = [
+        (Command::Restart, "play this seed again"),
+        (Command::NewGame, "new game"),
+        (Command::NewSettings, "new settings"),
+    ]
+    .into_iter()
+    .filter_map(|(command, what)| Some(format!("{}: {}", keymap.key(command)?, what)))
+    .collect();
+
+    options.join(", ")
+}
+
 /// Create a window for the game.
 fn create_window(
     title: &str,
@@ -251,50 +461,54 @@ fn create_window(
     )
 }
 
-fn draw(field: &Field, screen: &mut [u8]) {
-    //assert_eq!(screen.len(), 4 * field.cells.len());
-    for (c, pix) in field.cells().iter().zip(screen.chunks_exact_mut(4)) {
-        // Terminal color scheme
-        /*
-        let color = match c.cell_state() {
-            CellState::Hidden => [0, 0, 0, 0],
-            CellState::Flagged => [190, 0, 20, 0],
-            CellState::Neighbours(n) => match n {
-                0 => [214, 214, 214, 0],
-                1 => [0, 118, 117, 0],
-                2 => [76, 74, 117, 0],
-                3 => [116, 0, 118, 0],
-                4 => [206, 160, 113, 0],
-                5 => [160, 211, 112, 0],
-                6 => [160, 211, 112, 0],
-                7 => [214, 95, 97, 0],
-                8 => [70, 70, 70, 0],
-                _ => panic!("No more than 8 neighbours should be possible in 2D minesweeper."),
-            },
-            CellState::RevealedMine => [90, 0, 20, 0],
-        };
-        */
-        let color = match c.cell_state() {
-            CellState::Hidden => [20, 20, 20, 0],
-            CellState::Flagged => [214, 22, 63, 0],
-            CellState::Neighbours(n) => match n {
-                0 => [186, 186, 186, 0],
-                1 => [0, 0, 255, 0],
-                2 => [15, 112, 1, 0],
-                3 => [251, 0, 6, 0],
-                4 => [0, 0, 109, 0],
-                5 => [107, 0, 2, 0],
-                6 => [14, 110, 108, 0],
-                7 => [30, 30, 30, 0],
-                8 => [109, 109, 109, 0],
-                _ => panic!("No more than 8 neighbours should be possible in 2D minesweeper."),
-            },
-            CellState::RevealedMine => [90, 0, 20, 0],
-        };
-        pix.copy_from_slice(&color);
+/// Returns the pixel of the frame under the position in the window, if there is one.
+///
+/// The position is mapped through the scaling of `pixels`, which also accounts for the borders
+/// around the frame when the window does not have its ratio.
+fn pixel_at(pixels: &Pixels, position: PhysicalPosition<f64>) -> Option<(usize, usize)> {
+    pixels.window_pos_to_pixel(position.into()).ok()
+}
+
+/// Starts the new game, and fits the frame and window to the size of its field. Returns the message
+/// to show if the game could not be created.
+fn start(
+    new: io::Result<Field>,
+    f: &mut Field,
+    layout: &mut Layout,
+    pixels: &mut Pixels,
+    window: &Window,
+) -> Option<String> {
+    let new = match new {
+        Ok(new) => new,
+        Err(err) => return Some(format!("Mine — could not start a new game: {}", err)),
+    };
+
+    let new_layout = Layout::of(&new);
+    if (new_layout.width, new_layout.height) != (layout.width, layout.height) {
+        // Keep the scale of the window, such that the tiles keep their size.
+        let scale = (window.inner_size().width as f64 / layout.width as f64).max(1.0);
+        let (width, height) = (new_layout.width as f64, new_layout.height as f64);
+        pixels.resize_buffer(width as u32, height as u32);
+        window.set_min_inner_size(Some(
+            PhysicalSize::new(width, height).to_logical::<f64>(window.scale_factor()),
+        ));
+        window.set_inner_size(PhysicalSize::new(width * scale, height * scale));
     }
 
-    // TODO: Redo this shit.
-    let i = (field.cursor_pos_x() * 4) + (4 * (field.width() * field.cursor_pos_y()));
-    screen[i..(i + 4)].copy_from_slice(&[231, 185, 3, 0])
+    *layout = new_layout;
+    *f = new;
+    window.request_redraw();
+    None
+}
+
+/// Returns the statistics of the game that has ended. The first time, they are taken from the
+/// field and the game is added to the session and the records.
+fn finish(f: &Field, end_stats: &mut Option<Stats>, session: &mut Session) -> Stats {
+    *end_stats.get_or_insert_with(|| {
+        let stats = Stats::of(f);
+        if let Err(err) = session.finish(f, stats) {
+            eprintln!("could not record the game: {}", err)
+        }
+        stats
+    })
 }
@@ -0,0 +1,208 @@
+//! The heads-up bar above the field in the gui, like that of classic Minesweeper: the number of
+//! mines left on the left, the time on the right, and a face in between that tells how the game
+//! is going and starts a new game when it is clicked.
+
+use super::tiles::{
+    self, Canvas, Color, BLACK, BORDER, DIGITS, DIGIT_SCALE, HIDDEN, MINUS, SHADOW, TILE,
+};
+use mine::{Field, GameState};
+
+/// The height of the bar in pixels.
+const HEIGHT: usize = 32;
+/// The narrowest the window can be while still fitting the bar. Smaller fields are centered.
+const MIN_WIDTH: usize = 128;
+/// The space between the edge of the window and the counters.
+const MARGIN: usize = 5;
+/// The size of the face button.
+const FACE_SIZE: usize = 26;
+
+/// The number of digits of the counters, and the space they take.
+const COUNTER_DIGITS: usize = 3;
+const DIGIT_WIDTH: usize = 5 * DIGIT_SCALE;
+const DIGIT_HEIGHT: usize = 7 * DIGIT_SCALE;
+const DIGIT_GAP: usize = 2;
+const COUNTER_WIDTH: usize = COUNTER_DIGITS * (DIGIT_WIDTH + DIGIT_GAP) + DIGIT_GAP;
+const COUNTER_HEIGHT: usize = DIGIT_HEIGHT + 2 * DIGIT_GAP;
+
+const COUNTER: Color = [255, 20, 20, 255];
+const FACE: Color = [255, 222, 0, 255];
+
+const PLAYING: [&str; 12] = [
+    "...######...",
+    "..#yyyyyy#..",
+    ".#yyyyyyyy#.",
+    "#yy#yyyy#yy#",
+    "#yy#yyyy#yy#",
+    "#yyyyyyyyyy#",
+    "#yyyyyyyyyy#",
+    "#yy#yyyy#yy#",
+    "#yyy####yyy#",
+    ".#yyyyyyyy#.",
+    "..#yyyyyy#..",
+    "...######...",
+];
+const WON: [&str; 12] = [
+    "...######...",
+    "..#yyyyyy#..",
+    ".#yyyyyyyy#.",
+    "#y###yy###y#",
+    "#yy##yy##yy#",
+    "#yyyyyyyyyy#",
+    "#yyyyyyyyyy#",
+    "#yy#yyyy#yy#",
+    "#yyy####yyy#",
+    ".#yyyyyyyy#.",
+    "..#yyyyyy#..",
+    "...######...",
+];
+const LOST: [&str; 12] = [
+    "...######...",
+    "..#yyyyyy#..",
+    ".#yyyyyyyy#.",
+    "#y#y#yy#y#y#",
+    "#yy#yyyy#yy#",
+    "#y#y#yy#y#y#",
+    "#yyyyyyyyyy#",
+    "#yyy####yyy#",
+    "#yy#yyyy#yy#",
+    ".#yyyyyyyy#.",
+    "..#yyyyyy#..",
+    "...######...",
+];
+const FACE_PALETTE: &[(char, Color)] = &[('#', BLACK), ('y', FACE)];
+
+/// Where the bar and the field are drawn in the frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Layout {
+    /// The width of the frame in pixels.
+    pub width: usize,
+    /// The height of the frame in pixels.
+    pub height: usize,
+    /// The top left corner of the field.
+    field: (usize, usize),
+}
+
+impl Layout {
+    /// Returns the layout for the field: the bar on top, and the field below it.
+    pub fn of(field: &Field) -> Self {
+        let field_width = field.width() * TILE;
+        let width = field_width.max(MIN_WIDTH);
+
+        Self {
+            width,
+            height: HEIGHT + field.height() * TILE,
+            field: ((width - field_width) / 2, HEIGHT),
+        }
+    }
+
+    /// Returns the position of the cell at the pixel, if it is on the field.
+    pub fn cell_at(&self, (x, y): (usize, usize), field: &Field) -> Option<(usize, usize)> {
+        let x = x.checked_sub(self.field.0)? / TILE;
+        let y = y.checked_sub(self.field.1)? / TILE;
+
+        (x < field.width() && y < field.height()).then_some((x, y))
+    }
+
+    /// Returns whether the pixel is on the face button.
+    pub fn is_on_face(&self, (x, y): (usize, usize)) -> bool {
+        let (face_x, face_y) = self.face();
+        (face_x..face_x + FACE_SIZE).contains(&x) && (face_y..face_y + FACE_SIZE).contains(&y)
+    }
+
+    /// Returns the top left corner of the face button.
+    fn face(&self) -> (usize, usize) {
+        ((self.width - FACE_SIZE) / 2, (HEIGHT - FACE_SIZE) / 2)
+    }
+}
+
+/// Draws the bar and the field to the frame.
+pub fn draw(field: &Field, probabilities: Option<&[f64]>, layout: &Layout, frame: &mut [u8]) {
+    let mut canvas = Canvas::new(frame, layout.width);
+
+    // The background of the bar, and of the sides of a narrow field.
+    canvas.fill(0, 0, layout.width, layout.height, HIDDEN);
+
+    let counter_y = (HEIGHT - COUNTER_HEIGHT) / 2;
+    counter(&mut canvas, MARGIN, counter_y, field.mines_left());
+    let seconds = field.elapsed().as_secs() as isize;
+    let timer_x = layout.width - MARGIN - COUNTER_WIDTH;
+    counter(&mut canvas, timer_x, counter_y, seconds);
+
+    let (face_x, face_y) = layout.face();
+    let face = match field.game_state() {
+        GameState::Running => &PLAYING,
+        GameState::Won => &WON,
+        GameState::GameOver => &LOST,
+    };
+    tiles::raised(&mut canvas, face_x, face_y, FACE_SIZE, HIDDEN);
+    canvas.centered(face_x, face_y, FACE_SIZE, face, 2, FACE_PALETTE);
+
+    // A sunken line between the bar and the field.
+    canvas.fill(0, HEIGHT - BORDER, layout.width, BORDER, SHADOW);
+
+    tiles::draw(field, probabilities, &mut canvas, layout.field);
+}
+
+/// Draws a counter of three red digits on black. Values that do not fit are clamped, and negative
+/// values are drawn with a minus sign.
+fn counter(canvas: &mut Canvas, x: usize, y: usize, value: isize) {
+    canvas.fill(x, y, COUNTER_WIDTH, COUNTER_HEIGHT, BLACK);
+
+    let text = if value < 0 {
+        format!("-{:02}", (-value).min(99))
+    } else {
+        format!("{:03}", value.min(999))
+    };
+    let palette = [('#', COUNTER)];
+    for (i, c) in text.chars().enumerate() {
+        let bitmap = match c.to_digit(10) {
+            Some(digit) => &DIGITS[digit as usize],
+            None => &MINUS,
+        };
+        let digit_x = x + DIGIT_GAP + i * (DIGIT_WIDTH + DIGIT_GAP);
+        canvas.bitmap(digit_x, y + DIGIT_GAP, bitmap, DIGIT_SCALE, &palette);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_under_the_mouse() {
+        // A field narrower than the bar is centered below it.
+        let f = Field::from_rows(&["...", "...", "..*"]).unwrap();
+        let layout = Layout::of(&f);
+        let left = (MIN_WIDTH - 3 * TILE) / 2;
+        let right = left + 3 * TILE;
+
+        assert_eq!(layout.cell_at((left, HEIGHT), &f), Some((0, 0)));
+        assert_eq!(
+            layout.cell_at((left + TILE - 1, HEIGHT + TILE), &f),
+            Some((0, 1))
+        );
+        assert_eq!(
+            layout.cell_at((right - 1, layout.height - 1), &f),
+            Some((2, 2))
+        );
+
+        // The bar, the sides of the field, and past its bottom.
+        assert_eq!(layout.cell_at((left, HEIGHT - 1), &f), None);
+        assert_eq!(layout.cell_at((left - 1, HEIGHT), &f), None);
+        assert_eq!(layout.cell_at((right, HEIGHT), &f), None);
+        assert_eq!(layout.cell_at((left, layout.height), &f), None);
+    }
+
+    #[test]
+    fn face_in_the_middle_of_the_bar() {
+        let f = Field::from_rows(&[&".".repeat(30)]).unwrap();
+        let layout = Layout::of(&f);
+        assert_eq!(layout.width, 30 * TILE);
+
+        let center = (layout.width / 2, HEIGHT / 2);
+        assert!(layout.is_on_face(center));
+        assert!(!layout.is_on_face((center.0 - FACE_SIZE, center.1)));
+        assert!(!layout.is_on_face((center.0, HEIGHT)));
+        assert_eq!(layout.cell_at(center, &f), None);
+    }
+}
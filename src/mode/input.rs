@@ -0,0 +1,371 @@
+//! Turning key presses and mouse events into actions, shared by the terminal and graphical
+//! interface.
+//!
+//! Revealing around a cell, a chord, can be done in a few ways: with its own key, by pressing
+//! reveal twice on the same cell in a short time, by revealing a number that is already revealed,
+//! and with the mouse, see [`Mouse`]. The time and the chords on numbers are set in the `[input]`
+//! section of the configuration file:
+//!
+//! ```toml
+//! [input]
+//! # Milliseconds in which a second reveal on the same cell reveals around it, 0 to turn it off.
+//! double-press = 400
+//! # Whether revealing a number that is already revealed reveals around it.
+//! reveal-chords = true
+//! ```
+
+use super::keymap::{self, Command};
+use super::mouse::{Mouse, MouseEvent};
+use mine::{Action, Field};
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// How presses are turned into actions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct InputConfig {
+    /// The time in which a second reveal on the same cell reveals around it. A zero time turns
+    /// double presses off.
+    pub double_press: Duration,
+    /// Whether revealing a number that is already revealed reveals around it.
+    pub reveal_chords: bool,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            double_press: Duration::from_millis(400),
+            reveal_chords: true,
+        }
+    }
+}
+
+impl InputConfig {
+    /// Returns the settings of the configuration file, or the default ones if there is no
+    /// configuration file.
+    pub fn load() -> io::Result<Self> {
+        Ok(keymap::load_config()?.unwrap_or_default())
+    }
+}
+
+/// Parses the `[input]` section of the configuration file, see the module documentation.
+impl FromStr for InputConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = Self::default();
+
+        for entry in keymap::entries(s)?
+            .iter()
+            .filter(|entry| entry.section == "input")
+        {
+            let err = |err: String| format!("line {}: {}", entry.line, err);
+            match entry.name {
+                "double-press" => {
+                    let millis = entry.value.parse().map_err(|_| {
+                        err(format!("expected milliseconds, found '{}'", entry.value))
+                    })?;
+                    config.double_press = Duration::from_millis(millis);
+                }
+                "reveal-chords" => {
+                    config.reveal_chords = entry.value.parse().map_err(|_| {
+                        err(format!("expected true or false, found '{}'", entry.value))
+                    })?;
+                }
+                name => return Err(err(format!("unknown setting '{}'", name))),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// A press of the player, as far as it is about the field.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputEvent {
+    /// A key bound to the command was pressed.
+    Key(Command),
+    /// The mouse did something at the cell, which is `None` if the mouse is not on the field.
+    Mouse(MouseEvent, Option<(usize, usize)>),
+}
+
+/// Turns the presses of the player into actions on the field.
+pub struct Input {
+    config: InputConfig,
+    mouse: Mouse,
+    /// The time and cell of the last reveal by key, unless something else was done after it.
+    last_reveal: Option<(Instant, (usize, usize))>,
+}
+
+impl Input {
+    pub fn new(config: InputConfig) -> Self {
+        Self {
+            config,
+            mouse: Mouse::default(),
+            last_reveal: None,
+        }
+    }
+
+    /// Returns the actions for the event, which happened at `now`, in the order they are to be
+    /// applied.
+    pub fn actions(&mut self, event: InputEvent, field: &Field, now: Instant) -> Vec<Action> {
+        let reveal = self.last_reveal.take();
+
+        match event {
+            InputEvent::Key(Command::Reveal) => {
+                let cell = (field.cursor_pos_x(), field.cursor_pos_y());
+                // The first press has revealed the cell already, a second one in time reveals
+                // around it.
+                let double_press = reveal.is_some_and(|(time, revealed)| {
+                    revealed == cell && now.duration_since(time) < self.config.double_press
+                });
+
+                if double_press || self.is_chord(field, cell) {
+                    vec![Action::RevealAround]
+                } else {
+                    self.last_reveal = Some((now, cell));
+                    vec![Action::Reveal]
+                }
+            }
+            InputEvent::Key(command) => command.action().into_iter().collect(),
+
+            InputEvent::Mouse(event, cell) => {
+                // Moving the mouse over the field does not come in between two presses of a key,
+                // unless it moves the cursor.
+                if event == MouseEvent::Move {
+                    self.last_reveal = reveal;
+                }
+
+                let actions = self.mouse.actions(event, cell, field);
+                if actions
+                    .iter()
+                    .any(|action| matches!(action, Action::CursorTo(..)))
+                {
+                    self.last_reveal = None;
+                }
+
+                actions
+                    .into_iter()
+                    .map(|action| match (action, cell) {
+                        (Action::Reveal, Some(cell)) if self.is_chord(field, cell) => {
+                            Action::RevealAround
+                        }
+                        _ => action,
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Forgets the last reveal, such that the next one is not a double press. This is needed when
+    /// something else happens in between, like a new game.
+    pub fn interrupt(&mut self) {
+        self.last_reveal = None;
+    }
+
+    /// Returns whether revealing the cell reveals around it instead, because it is a revealed
+    /// number.
+    fn is_chord(&self, field: &Field, (x, y): (usize, usize)) -> bool {
+        let cell = field.get_cell(x, y);
+        self.config.reveal_chords && cell.is_revealed() && !cell.is_mine() && cell.neighbours() > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mode::mouse::Button;
+
+    /// A 3 by 3 field with a mine in the bottom right corner and the cursor in the middle, on a 1.
+    fn field() -> Field {
+        let mut f = Field::from_rows(&["...", "...", "..*"]).unwrap();
+        f.apply_action(Action::CursorTo(1, 1));
+        f
+    }
+
+    fn key(input: &mut Input, command: Command, f: &Field, at: Instant) -> Vec<Action> {
+        input.actions(InputEvent::Key(command), f, at)
+    }
+
+    #[test]
+    fn second_reveal_in_time_reveals_around() {
+        let f = field();
+        let start = Instant::now();
+        let mut input = Input::new(InputConfig::default());
+
+        assert_eq!(
+            key(&mut input, Command::Reveal, &f, start),
+            [Action::Reveal]
+        );
+        let soon = start + Duration::from_millis(100);
+        assert_eq!(
+            key(&mut input, Command::Reveal, &f, soon),
+            [Action::RevealAround]
+        );
+
+        // A third press starts over, as does a press after the time.
+        let later = soon + Duration::from_millis(100);
+        assert_eq!(
+            key(&mut input, Command::Reveal, &f, later),
+            [Action::Reveal]
+        );
+        let late = later + Duration::from_millis(400);
+        assert_eq!(key(&mut input, Command::Reveal, &f, late), [Action::Reveal]);
+    }
+
+    #[test]
+    fn other_keys_come_in_between() {
+        let f = field();
+        let start = Instant::now();
+        let mut input = Input::new(InputConfig::default());
+
+        key(&mut input, Command::Reveal, &f, start);
+        key(&mut input, Command::Heatmap, &f, start);
+        assert_eq!(
+            key(&mut input, Command::Reveal, &f, start),
+            [Action::Reveal]
+        );
+
+        key(&mut input, Command::Reveal, &f, start);
+        input.interrupt();
+        assert_eq!(
+            key(&mut input, Command::Reveal, &f, start),
+            [Action::Reveal]
+        );
+    }
+
+    #[test]
+    fn double_press_can_be_turned_off() {
+        let f = field();
+        let start = Instant::now();
+        let mut input = Input::new(InputConfig {
+            double_press: Duration::ZERO,
+            ..InputConfig::default()
+        });
+
+        key(&mut input, Command::Reveal, &f, start);
+        assert_eq!(
+            key(&mut input, Command::Reveal, &f, start),
+            [Action::Reveal]
+        );
+    }
+
+    #[test]
+    fn revealing_a_number_reveals_around_it() {
+        let mut f = field();
+        f.apply_action(Action::Reveal);
+        let now = Instant::now();
+
+        let mut input = Input::new(InputConfig::default());
+        assert_eq!(
+            key(&mut input, Command::Reveal, &f, now),
+            [Action::RevealAround]
+        );
+        let event = InputEvent::Mouse(MouseEvent::Release(Button::Left), Some((1, 1)));
+        input.actions(
+            InputEvent::Mouse(MouseEvent::Press(Button::Left), Some((1, 1))),
+            &f,
+            now,
+        );
+        assert_eq!(input.actions(event, &f, now), [Action::RevealAround]);
+
+        let mut input = Input::new(InputConfig {
+            reveal_chords: false,
+            ..InputConfig::default()
+        });
+        assert_eq!(key(&mut input, Command::Reveal, &f, now), [Action::Reveal]);
+    }
+
+    #[test]
+    fn mouse_moves_the_cursor_before_acting() {
+        let mut f = field();
+        let now = Instant::now();
+        let mut input = Input::new(InputConfig::default());
+        let mut mouse = |f: &mut Field, event, cell| {
+            let actions = input.actions(InputEvent::Mouse(event, cell), f, now);
+            for &action in &actions {
+                f.apply_action(action);
+            }
+            actions
+        };
+
+        let right = (
+            MouseEvent::Press(Button::Right),
+            MouseEvent::Release(Button::Right),
+        );
+        assert_eq!(
+            mouse(&mut f, right.0, Some((0, 2))),
+            [Action::CursorTo(0, 2)]
+        );
+        assert_eq!(mouse(&mut f, right.1, Some((0, 2))), [Action::Flag]);
+
+        // Pressing both buttons and releasing them reveals around the cell.
+        let left = (
+            MouseEvent::Press(Button::Left),
+            MouseEvent::Release(Button::Left),
+        );
+        assert_eq!(
+            mouse(&mut f, left.0, Some((1, 1))),
+            [Action::CursorTo(1, 1)]
+        );
+        assert_eq!(mouse(&mut f, right.0, Some((1, 1))), []);
+        assert_eq!(mouse(&mut f, left.1, Some((1, 1))), []);
+        assert_eq!(mouse(&mut f, right.1, Some((1, 1))), [Action::RevealAround]);
+
+        assert_eq!(mouse(&mut f, MouseEvent::Press(Button::Middle), None), []);
+    }
+
+    #[test]
+    fn moving_the_mouse_in_place_keeps_a_double_press() {
+        let f = field();
+        let start = Instant::now();
+        let mut input = Input::new(InputConfig::default());
+
+        key(&mut input, Command::Reveal, &f, start);
+        let event = InputEvent::Mouse(MouseEvent::Move, Some((1, 1)));
+        assert_eq!(input.actions(event, &f, start), []);
+        assert_eq!(
+            key(&mut input, Command::Reveal, &f, start),
+            [Action::RevealAround]
+        );
+
+        key(&mut input, Command::Reveal, &f, start);
+        let event = InputEvent::Mouse(MouseEvent::Move, Some((0, 0)));
+        assert_eq!(input.actions(event, &f, start), [Action::CursorTo(0, 0)]);
+        assert_eq!(
+            key(&mut input, Command::Reveal, &f, start),
+            [Action::Reveal]
+        );
+    }
+
+    #[test]
+    fn configuration() {
+        let config: InputConfig =
+            "[keys]\nflag = \"x\"\n[input]\ndouble-press = 250\nreveal-chords = false\n"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            config,
+            InputConfig {
+                double_press: Duration::from_millis(250),
+                reveal_chords: false,
+            }
+        );
+
+        for (config, error) in [
+            (
+                "[input]\ndouble-press = soon\n",
+                "line 2: expected milliseconds, found 'soon'",
+            ),
+            (
+                "[input]\nreveal-chords = 1\n",
+                "line 2: expected true or false, found '1'",
+            ),
+            (
+                "[input]\ntriple-press = 1\n",
+                "line 2: unknown setting 'triple-press'",
+            ),
+        ] {
+            assert_eq!(config.parse::<InputConfig>().err().as_deref(), Some(error));
+        }
+    }
+}
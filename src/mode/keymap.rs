@@ -0,0 +1,825 @@
+//! The keys that play the game, shared by the terminal and graphical interface.
+//!
+//! Both interfaces turn their own key events into a [`KeyPress`], and look up the [`Command`] it
+//! is bound to in the keymap. The keymap starts from a preset layout, which the configuration file
+//! `~/.config/mine/config.toml` can choose and change:
+//!
+//! ```toml
+//! [keys]
+//! # vim (the default), wasd or numpad
+//! preset = "vim"
+//! # Replaces the keys of the preset for these commands.
+//! flag = ["f", "Space", "Shift+Enter"]
+//! redo = "Ctrl+r"
+//! ```
+//!
+//! A key is written as a character, like `k`, `K` or `$`, or as the name of a key: `Up`, `Down`,
+//! `Left`, `Right`, `Enter`, `Tab`, `Space`, `Escape`, `Backspace`, `Home`, `End`, `PageUp` or
+//! `PageDown`. It can be preceded by modifiers, like `Ctrl+r` or `Shift+Up`. Characters are the
+//! characters that are typed, so `$` is `Shift+4` on a US keyboard, and `K` is the same as
+//! `Shift+k`. The terminal cannot tell whether alt is held, nor whether shift is held with keys
+//! other than characters.
+//!
+//! The configuration is read with a small part of TOML: sections, strings and arrays of strings.
+
+use mine::{paths, Action};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+
+/// What a key can be bound to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    Up,
+    Down,
+    Left,
+    Right,
+    EdgeUp,
+    EdgeDown,
+    EdgeLeft,
+    EdgeRight,
+    Flag,
+    /// Reveals the cell, or around it when pressed twice or on a number, see [`Input`].
+    ///
+    /// [`Input`]: super::input::Input
+    Reveal,
+    /// Reveals around the cell.
+    Chord,
+    Hint,
+    Heatmap,
+    Undo,
+    Redo,
+    Save,
+    /// Plays the same seed again, once the game has ended.
+    Restart,
+    NewGame,
+    NewSettings,
+    Quit,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+}
+
+/// The names of the commands in the configuration file, in the order of `Command`.
+const COMMANDS: [(&str, Command); 24] = [
+    ("up", Command::Up),
+    ("down", Command::Down),
+    ("left", Command::Left),
+    ("right", Command::Right),
+    ("edge-up", Command::EdgeUp),
+    ("edge-down", Command::EdgeDown),
+    ("edge-left", Command::EdgeLeft),
+    ("edge-right", Command::EdgeRight),
+    ("flag", Command::Flag),
+    ("reveal", Command::Reveal),
+    ("chord", Command::Chord),
+    ("hint", Command::Hint),
+    ("heatmap", Command::Heatmap),
+    ("undo", Command::Undo),
+    ("redo", Command::Redo),
+    ("save", Command::Save),
+    ("restart", Command::Restart),
+    ("new-game", Command::NewGame),
+    ("new-settings", Command::NewSettings),
+    ("quit", Command::Quit),
+    ("half-page-down", Command::HalfPageDown),
+    ("half-page-up", Command::HalfPageUp),
+    ("page-down", Command::PageDown),
+    ("page-up", Command::PageUp),
+];
+
+impl Command {
+    /// Returns the action on the field that the command is, if it is one. Revealing is left out,
+    /// since it depends on what was pressed before.
+    pub fn action(&self) -> Option<Action> {
+        match self {
+            Command::Up => Some(Action::CursorUp),
+            Command::Down => Some(Action::CursorDown),
+            Command::Left => Some(Action::CursorLeft),
+            Command::Right => Some(Action::CursorRight),
+            Command::EdgeUp => Some(Action::CursorToEdgeUp),
+            Command::EdgeDown => Some(Action::CursorToEdgeDown),
+            Command::EdgeLeft => Some(Action::CursorToEdgeLeft),
+            Command::EdgeRight => Some(Action::CursorToEdgeRight),
+            Command::Flag => Some(Action::Flag),
+            Command::Chord => Some(Action::RevealAround),
+            Command::Hint => Some(Action::Hint),
+            Command::Undo => Some(Action::Undo),
+            Command::Redo => Some(Action::Redo),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        COMMANDS
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, command)| *command)
+            .ok_or_else(|| format!("unknown command '{}'", s))
+    }
+}
+
+/// A key on the keyboard.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Key {
+    /// A character as it is typed. Letters are lowercase, with shift held for uppercase.
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Tab,
+    Space,
+    Escape,
+    Backspace,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+/// The names of the keys that are not characters.
+const KEYS: [(&str, Key); 13] = [
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+    ("Enter", Key::Enter),
+    ("Tab", Key::Tab),
+    ("Space", Key::Space),
+    ("Escape", Key::Escape),
+    ("Backspace", Key::Backspace),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+];
+
+/// A key with the modifiers held while pressing it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyPress {
+    pub key: Key,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl KeyPress {
+    /// Returns the key press, in the form in which it is bound: uppercase letters become
+    /// lowercase letters with shift held, and shift is left out of other characters, which
+    /// already tell whether it was held. Control with a letter is the same in either case, as
+    /// the terminal cannot tell them apart.
+    pub fn new(key: Key, ctrl: bool, alt: bool, shift: bool) -> Self {
+        let (key, shift) = match key {
+            Key::Char(c) if c.is_uppercase() => (Key::Char(c.to_ascii_lowercase()), !ctrl),
+            Key::Char(c) if c.is_lowercase() => (key, shift && !ctrl),
+            Key::Char(_) => (key, false),
+            _ => (key, shift),
+        };
+
+        Self {
+            key,
+            ctrl,
+            alt,
+            shift,
+        }
+    }
+
+    /// Returns the key press without modifiers.
+    #[cfg(feature = "tui")]
+    fn plain(key: Key) -> Self {
+        Self::new(key, false, false, false)
+    }
+
+    /// Returns whether the key press types a character, which is then also received as text.
+    #[cfg(feature = "gui")]
+    pub fn is_typed(&self) -> bool {
+        matches!(self.key, Key::Char(_) | Key::Space) && !self.ctrl && !self.alt
+    }
+}
+
+/// Parses a key like `k`, `K`, `Ctrl+r` or `Shift+Up`.
+impl FromStr for KeyPress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The last part is the key, which can be a plus itself.
+        let (modifiers, key) = match s.strip_suffix('+') {
+            Some(modifiers) if modifiers.is_empty() || modifiers.ends_with('+') => {
+                (modifiers.trim_end_matches('+'), "+")
+            }
+            _ => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+
+        let (mut ctrl, mut alt, mut shift) = (false, false, false);
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => ctrl = true,
+                "alt" => alt = true,
+                "shift" => shift = true,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, s)),
+            }
+        }
+
+        let mut chars = key.chars();
+        let key = match (chars.next(), chars.next()) {
+            (Some(c), None) => Key::Char(c),
+            _ => KEYS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, key)| *key)
+                .ok_or_else(|| format!("unknown key '{}'", key))?,
+        };
+
+        Ok(Self::new(key, ctrl, alt, shift))
+    }
+}
+
+/// Writes the key press as it is written in the configuration file, like `K` or `Ctrl+r`.
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        match self.key {
+            // Shift is only held with a letter, which is then written in uppercase.
+            Key::Char(c) if self.shift => write!(f, "{}", c.to_uppercase()),
+            Key::Char(c) => write!(f, "{}", c),
+            key => {
+                if self.shift {
+                    write!(f, "Shift+")?;
+                }
+                let (name, _) = KEYS
+                    .iter()
+                    .find(|(_, named)| *named == key)
+                    .expect("every key other than a character has a name");
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+/// The layouts of keys to start from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Preset {
+    /// `h`, `j`, `k` and `l` to move, and the arrows.
+    Vim,
+    /// `w`, `a`, `s` and `d` to move, and the arrows, with the space bar to reveal.
+    Wasd,
+    /// The number pad, with or without num lock.
+    Numpad,
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vim" => Ok(Preset::Vim),
+            "wasd" => Ok(Preset::Wasd),
+            "numpad" => Ok(Preset::Numpad),
+            _ => Err(format!(
+                "unknown preset '{}', expected vim, wasd or numpad",
+                s
+            )),
+        }
+    }
+}
+
+impl Preset {
+    /// Returns the keys of the preset for every command.
+    fn bindings(&self) -> Vec<(Command, &'static [&'static str])> {
+        use Command::*;
+
+        // The keys that all presets share, unless they bind them otherwise.
+        let common: Vec<(Command, &[&str])> = vec![
+            (Flag, &["f", "Space"]),
+            (Reveal, &["r", "Enter", "Tab"]),
+            (Chord, &["c"]),
+            (Hint, &["?", "/"]),
+            (Heatmap, &["p"]),
+            (Undo, &["u"]),
+            (Redo, &["Ctrl+r"]),
+            (Save, &["s"]),
+            (Restart, &["R"]),
+            (NewGame, &["n"]),
+            (NewSettings, &["N"]),
+            (Quit, &["q"]),
+            (HalfPageDown, &["Ctrl+d"]),
+            (HalfPageUp, &["Ctrl+u"]),
+            (PageDown, &["Ctrl+f"]),
+            (PageUp, &["Ctrl+b"]),
+        ];
+
+        let own: Vec<(Command, &[&str])> = match self {
+            Preset::Vim => vec![
+                (Up, &["k", "Up"]),
+                (Down, &["j", "Down"]),
+                (Left, &["h", "Left"]),
+                (Right, &["l", "Right"]),
+                (EdgeUp, &["K", "g", "Shift+Up"]),
+                (EdgeDown, &["J", "G", "Shift+Down"]),
+                (EdgeLeft, &["H", "0", "Shift+Left"]),
+                (EdgeRight, &["L", "$", "Shift+Right"]),
+            ],
+            Preset::Wasd => vec![
+                (Up, &["w", "Up"]),
+                (Down, &["s", "Down"]),
+                (Left, &["a", "Left"]),
+                (Right, &["d", "Right"]),
+                (EdgeUp, &["W", "Shift+Up"]),
+                (EdgeDown, &["S", "Shift+Down"]),
+                (EdgeLeft, &["A", "Shift+Left"]),
+                (EdgeRight, &["D", "Shift+Right"]),
+                (Flag, &["e", "f"]),
+                (Reveal, &["Space", "Enter", "r"]),
+                (Save, &["Ctrl+s"]),
+                (Quit, &["Ctrl+q", "Escape"]),
+            ],
+            Preset::Numpad => vec![
+                (Up, &["8", "Up"]),
+                (Down, &["2", "Down"]),
+                (Left, &["4", "Left"]),
+                (Right, &["6", "Right"]),
+                (EdgeUp, &["9", "PageUp"]),
+                (EdgeDown, &["3", "PageDown"]),
+                (EdgeLeft, &["7", "Home"]),
+                (EdgeRight, &["1", "End"]),
+                (Flag, &["0", "f", "Space"]),
+                (Reveal, &["5", "Enter", "r"]),
+                (Hint, &[".", "?"]),
+                (Heatmap, &["*", "p"]),
+                (Undo, &["-", "u"]),
+                (Redo, &["+", "Ctrl+r"]),
+            ],
+        };
+
+        // The own keys of the preset replace the common ones of the same command.
+        let mut bindings: Vec<(Command, &[&str])> = common
+            .into_iter()
+            .filter(|(command, _)| !own.iter().any(|(own, _)| own == command))
+            .collect();
+        bindings.extend(own);
+        bindings
+    }
+}
+
+/// The commands that keys are bound to.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(KeyPress, Command)>,
+}
+
+impl Keymap {
+    /// Returns the keymap of the preset.
+    pub fn preset(preset: Preset) -> Self {
+        let bindings = preset
+            .bindings()
+            .into_iter()
+            .flat_map(|(command, keys)| {
+                keys.iter().map(move |key| {
+                    let key = key.parse().expect("the keys of the presets are valid");
+                    (key, command)
+                })
+            })
+            .collect();
+
+        Self { bindings }
+    }
+
+    /// Returns the keymap of the configuration file, or that of the vim preset if there is no
+    /// configuration file.
+    pub fn load() -> io::Result<Self> {
+        Ok(load_config()?.unwrap_or_else(|| Self::preset(Preset::Vim)))
+    }
+
+    /// Returns the command that the key press is bound to.
+    pub fn command(&self, press: &KeyPress) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(key, _)| key == press)
+            .map(|(_, command)| *command)
+    }
+
+    /// Returns the first key the command is bound to, to show to the player, or `None` if it is
+    /// not bound to any key.
+    pub fn key(&self, command: Command) -> Option<KeyPress> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == command)
+            .map(|(key, _)| *key)
+    }
+
+    /// Binds the command to the keys, instead of the keys it was bound to. The keys are taken from
+    /// the commands they were bound to.
+    fn bind(&mut self, command: Command, keys: &[KeyPress]) {
+        self.bindings
+            .retain(|(key, bound)| *bound != command && !keys.contains(key));
+        self.bindings.extend(keys.iter().map(|key| (*key, command)));
+    }
+}
+
+/// Parses the configuration file, see the module documentation.
+impl FromStr for Keymap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut preset = Preset::Vim;
+        let mut bindings = Vec::new();
+
+        // Other sections are left for other settings.
+        for entry in entries(s)?.iter().filter(|entry| entry.section == "keys") {
+            let err = |err: String| format!("line {}: {}", entry.line, err);
+            let values = parse_value(entry.value).map_err(err)?;
+            if entry.name == "preset" {
+                preset = match &values[..] {
+                    [preset] => preset.parse().map_err(err)?,
+                    _ => return Err(err("expected a single preset".to_string())),
+                };
+            } else {
+                let command: Command = entry.name.parse().map_err(err)?;
+                let keys = values
+                    .iter()
+                    .map(|key| key.parse())
+                    .collect::<Result<Vec<KeyPress>, _>>()
+                    .map_err(err)?;
+                bindings.push((command, keys));
+            }
+        }
+
+        let mut keymap = Self::preset(preset);
+        for (command, keys) in bindings {
+            keymap.bind(command, &keys);
+        }
+        Ok(keymap)
+    }
+}
+
+/// Reads the configuration file and parses the settings of `T` from it. Returns `None` if there is
+/// no configuration file.
+pub fn load_config<T: FromStr<Err = String>>() -> io::Result<Option<T>> {
+    let path = match paths::config() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(None),
+    };
+
+    fs::read_to_string(&path)?.parse().map(Some).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid configuration {}: {}", path.display(), err),
+        )
+    })
+}
+
+/// A `name = value` line of the configuration file.
+pub struct Entry<'c> {
+    /// The number of the line, from 1.
+    pub line: usize,
+    /// The section the line is in, or the empty string before the first section.
+    pub section: &'c str,
+    pub name: &'c str,
+    /// The value as it is written, without the comment after it.
+    pub value: &'c str,
+}
+
+/// Returns the `name = value` lines of the configuration file, along with their sections.
+pub fn entries(s: &str) -> Result<Vec<Entry<'_>>, String> {
+    let mut section = "";
+    let mut entries = Vec::new();
+
+    for (number, line) in s.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim();
+            continue;
+        }
+
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected 'name = value'", number + 1))?;
+        entries.push(Entry {
+            line: number + 1,
+            section,
+            name: name.trim(),
+            value: value.trim(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Returns the line up to a `#` that is not in a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Parses a string like `"k"`, or an array of strings like `["k", "Up"]`.
+fn parse_value(value: &str) -> Result<Vec<String>, String> {
+    let mut strings = Vec::new();
+    let inner = match value.strip_prefix('[') {
+        Some(array) => array
+            .strip_suffix(']')
+            .ok_or_else(|| format!("expected ']' at the end of '{}'", value))?,
+        None => value,
+    };
+
+    let mut chars = inner.trim().chars().peekable();
+    loop {
+        match chars.next() {
+            None => break,
+            Some('"') => {}
+            Some(c) => return Err(format!("expected a string, found '{}'", c)),
+        }
+
+        let mut string = String::new();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some(c @ ('"' | '\\')) => string.push(c),
+                    _ => return Err("unknown escape in a string".to_string()),
+                },
+                Some(c) => string.push(c),
+                None => return Err("a string is not closed".to_string()),
+            }
+        }
+        strings.push(string);
+
+        // Strings in an array are separated by commas.
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.next() {
+            None => break,
+            Some(',') if value.starts_with('[') => {
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            }
+            Some(c) => return Err(format!("expected ',' or the end, found '{}'", c)),
+        }
+    }
+
+    if strings.is_empty() && !value.starts_with('[') {
+        return Err("expected a string or an array of strings".to_string());
+    }
+    Ok(strings)
+}
+
+/// Turns the keys of the terminal into key presses.
+#[cfg(feature = "tui")]
+impl KeyPress {
+    /// Returns the key press of a key read from the terminal, if it can be bound.
+    pub fn from_console(key: &console::Key) -> Option<Self> {
+        use console::Key as K;
+
+        let key = match key {
+            // Control with a letter is read as a control character, from 1 for a to 26 for z.
+            K::Char(c @ '\u{1}'..='\u{1a}') => {
+                let letter = (b'a' + *c as u8 - 1) as char;
+                return Some(Self::new(Key::Char(letter), true, false, false));
+            }
+            K::Char(' ') => Key::Space,
+            K::Char(c) if !c.is_control() => Key::Char(*c),
+            K::ArrowUp => Key::Up,
+            K::ArrowDown => Key::Down,
+            K::ArrowLeft => Key::Left,
+            K::ArrowRight => Key::Right,
+            K::Enter => Key::Enter,
+            K::Tab => Key::Tab,
+            K::Escape => Key::Escape,
+            K::Backspace => Key::Backspace,
+            K::Home => Key::Home,
+            K::End => Key::End,
+            K::PageUp => Key::PageUp,
+            K::PageDown => Key::PageDown,
+            _ => return None,
+        };
+        Some(Self::plain(key))
+    }
+}
+
+/// Turns the keys of the window into key presses.
+#[cfg(feature = "gui")]
+impl KeyPress {
+    /// Returns the key press of a key pressed in the window, if it can be bound. Characters are
+    /// taken from a US keyboard layout.
+    pub fn from_winit(
+        code: winit::event::VirtualKeyCode,
+        modifiers: winit::event::ModifiersState,
+    ) -> Option<Self> {
+        use winit::event::VirtualKeyCode as V;
+
+        let shift = modifiers.shift();
+        // The character of the key, and with shift held.
+        let typed = |plain: char, shifted: char| Key::Char(if shift { shifted } else { plain });
+
+        let key = match code {
+            V::A
+            | V::B
+            | V::C
+            | V::D
+            | V::E
+            | V::F
+            | V::G
+            | V::H
+            | V::I
+            | V::J
+            | V::K
+            | V::L
+            | V::M
+            | V::N
+            | V::O
+            | V::P
+            | V::Q
+            | V::R
+            | V::S
+            | V::T
+            | V::U
+            | V::V
+            | V::W
+            | V::X
+            | V::Y
+            | V::Z => {
+                // The letters follow each other in the order of the alphabet.
+                let letter = (b'a' + (code as u8 - V::A as u8)) as char;
+                Key::Char(letter)
+            }
+            V::Key1 => typed('1', '!'),
+            V::Key2 => typed('2', '@'),
+            V::Key3 => typed('3', '#'),
+            V::Key4 => typed('4', '$'),
+            V::Key5 => typed('5', '%'),
+            V::Key6 => typed('6', '^'),
+            V::Key7 => typed('7', '&'),
+            V::Key8 => typed('8', '*'),
+            V::Key9 => typed('9', '('),
+            V::Key0 => typed('0', ')'),
+            V::Numpad0 => Key::Char('0'),
+            V::Numpad1 => Key::Char('1'),
+            V::Numpad2 => Key::Char('2'),
+            V::Numpad3 => Key::Char('3'),
+            V::Numpad4 => Key::Char('4'),
+            V::Numpad5 => Key::Char('5'),
+            V::Numpad6 => Key::Char('6'),
+            V::Numpad7 => Key::Char('7'),
+            V::Numpad8 => Key::Char('8'),
+            V::Numpad9 => Key::Char('9'),
+            V::NumpadAdd => Key::Char('+'),
+            V::NumpadSubtract => Key::Char('-'),
+            V::NumpadMultiply => Key::Char('*'),
+            V::NumpadDivide => Key::Char('/'),
+            V::NumpadDecimal => Key::Char('.'),
+            V::NumpadEnter => Key::Enter,
+            V::Minus => typed('-', '_'),
+            V::Equals => typed('=', '+'),
+            V::LBracket => typed('[', '{'),
+            V::RBracket => typed(']', '}'),
+            V::Backslash => typed('\\', '|'),
+            V::Semicolon => typed(';', ':'),
+            V::Apostrophe => typed('\'', '"'),
+            V::Grave => typed('`', '~'),
+            V::Comma => typed(',', '<'),
+            V::Period => typed('.', '>'),
+            V::Slash => typed('/', '?'),
+            V::Up => Key::Up,
+            V::Down => Key::Down,
+            V::Left => Key::Left,
+            V::Right => Key::Right,
+            V::Return => Key::Enter,
+            V::Tab => Key::Tab,
+            V::Space => Key::Space,
+            V::Escape => Key::Escape,
+            V::Back => Key::Backspace,
+            V::Home => Key::Home,
+            V::End => Key::End,
+            V::PageUp => Key::PageUp,
+            V::PageDown => Key::PageDown,
+            _ => return None,
+        };
+
+        Some(Self::new(key, modifiers.ctrl(), modifiers.alt(), shift))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(s: &str) -> KeyPress {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn key_presses() {
+        assert_eq!(press("K"), press("Shift+k"));
+        assert_eq!(press("Ctrl+R"), press("Ctrl+r"));
+        assert_eq!(press("+").key, Key::Char('+'));
+        assert_eq!(
+            press("Ctrl++"),
+            KeyPress::new(Key::Char('+'), true, false, false)
+        );
+        assert_eq!(
+            press("shift+up"),
+            KeyPress::new(Key::Up, false, false, true)
+        );
+        assert!("Hyper+k".parse::<KeyPress>().is_err());
+        assert!("Up2".parse::<KeyPress>().is_err());
+
+        for key in ["k", "K", "$", "Ctrl+r", "Shift+Up", "Alt+Enter", "Space"] {
+            assert_eq!(press(key).to_string(), key);
+        }
+    }
+
+    #[test]
+    fn presets() {
+        let vim = Keymap::preset(Preset::Vim);
+        assert_eq!(vim.command(&press("k")), Some(Command::Up));
+        assert_eq!(vim.command(&press("r")), Some(Command::Reveal));
+        assert_eq!(vim.key(Command::Restart), Some(press("R")));
+
+        let wasd = Keymap::preset(Preset::Wasd);
+        assert_eq!(wasd.command(&press("Space")), Some(Command::Reveal));
+        assert_eq!(wasd.command(&press("s")), Some(Command::Down));
+        assert_eq!(wasd.command(&press("Ctrl+s")), Some(Command::Save));
+
+        let numpad = Keymap::preset(Preset::Numpad);
+        assert_eq!(numpad.command(&press("5")), Some(Command::Reveal));
+        assert_eq!(numpad.command(&press("-")), Some(Command::Undo));
+    }
+
+    #[test]
+    fn configuration_replaces_the_keys_of_a_command() {
+        let keymap: Keymap =
+            "[keys]\npreset = \"wasd\"\nflag = [\"x\", \"Shift+Enter\"] # comment\n"
+                .parse()
+                .unwrap();
+        assert_eq!(keymap.command(&press("x")), Some(Command::Flag));
+        assert_eq!(keymap.command(&press("Shift+Enter")), Some(Command::Flag));
+        assert_eq!(keymap.command(&press("e")), None);
+        assert_eq!(keymap.command(&press("w")), Some(Command::Up));
+    }
+
+    #[test]
+    fn bound_keys_are_taken_from_other_commands() {
+        let keymap: Keymap = "[keys]\nflag = \"k\"\n".parse().unwrap();
+        assert_eq!(keymap.command(&press("k")), Some(Command::Flag));
+        assert_eq!(keymap.key(Command::Up), Some(press("Up")));
+    }
+
+    #[test]
+    fn other_sections_are_left_alone() {
+        let keymap: Keymap = "[input]\ndouble-press = 300\n[keys]\nundo = \"z\"\n"
+            .parse()
+            .unwrap();
+        assert_eq!(keymap.command(&press("z")), Some(Command::Undo));
+    }
+
+    #[test]
+    fn invalid_configurations() {
+        for (config, error) in [
+            ("[keys]\nfly = \"x\"\n", "line 2: unknown command 'fly'"),
+            ("[keys]\nflag = x\n", "line 2: expected a string, found 'x'"),
+            (
+                "[keys]\nflag = [\"x\"\n",
+                "line 2: expected ']' at the end of '[\"x\"'",
+            ),
+            (
+                "[keys]\npreset = \"emacs\"\n",
+                "line 2: unknown preset 'emacs', expected vim, wasd or numpad",
+            ),
+            ("[keys]\nflag\n", "line 2: expected 'name = value'"),
+        ] {
+            assert_eq!(config.parse::<Keymap>().err().as_deref(), Some(error));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tui")]
+    fn console_keys() {
+        let from = |key| KeyPress::from_console(&key);
+        assert_eq!(from(console::Key::Char('\u{12}')), Some(press("Ctrl+r")));
+        assert_eq!(from(console::Key::Char(' ')), Some(press("Space")));
+        assert_eq!(from(console::Key::Char('J')), Some(press("J")));
+        assert_eq!(from(console::Key::ArrowUp), Some(press("Up")));
+    }
+}
@@ -0,0 +1,65 @@
+//! Reading keys with a timeout.
+//!
+//! The terminal has no timeout on reading a key, so keys are read on a separate thread. A key is
+//! only read when one is asked for, such that the thread does not wait for a key while the
+//! terminal is read directly, as by the prompts, and does not leave the terminal in raw mode by
+//! waiting for a key after the last one has been read.
+//!
+//! `^C` is read as `Key::CtrlC` rather than raising `SIGINT`, which would end the game without
+//! restoring the terminal or saving the game.
+
+use console::{Key, Term};
+use std::io;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Reads keys from the terminal on a separate thread.
+pub struct Keys {
+    requests: Sender<()>,
+    keys: Receiver<io::Result<Key>>,
+    /// Whether a key has been asked for that has not been received yet.
+    pending: bool,
+}
+
+impl Keys {
+    pub fn new() -> Self {
+        let (requests, request_receiver) = mpsc::channel();
+        let (key_sender, keys) = mpsc::channel();
+        thread::spawn(move || {
+            let term = Term::stdout();
+            for () in request_receiver {
+                if key_sender.send(term.read_key_raw()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            requests,
+            keys,
+            pending: false,
+        }
+    }
+
+    /// Waits for the next key, for at most the timeout if there is one. Returns `None` if no key
+    /// was pressed in time; the key is then returned by the next call.
+    pub fn read_key(&mut self, timeout: Option<Duration>) -> io::Result<Option<Key>> {
+        let stopped = || io::Error::new(io::ErrorKind::BrokenPipe, "stopped reading keys");
+        if !self.pending {
+            self.requests.send(()).map_err(|_| stopped())?;
+            self.pending = true;
+        }
+
+        let key = match timeout {
+            Some(timeout) => match self.keys.recv_timeout(timeout) {
+                Ok(key) => key,
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => return Err(stopped()),
+            },
+            None => self.keys.recv().map_err(|_| stopped())?,
+        };
+        self.pending = false;
+        key.map(Some)
+    }
+}
@@ -1,4 +1,29 @@
 #[cfg(feature = "gui")]
 pub mod gui;
+#[cfg(feature = "gui")]
+mod hud;
+#[cfg(any(feature = "tui", feature = "gui"))]
+mod input;
+#[cfg(any(feature = "tui", feature = "gui"))]
+mod keymap;
+#[cfg(feature = "tui")]
+mod keys;
+#[cfg(any(feature = "tui", feature = "gui"))]
+mod mouse;
+#[cfg(feature = "tui")]
+pub mod replay;
+#[cfg(feature = "tui")]
+mod screen;
+#[cfg(feature = "tui")]
+mod sgr;
+pub mod stats;
+#[cfg(feature = "tui")]
+mod styled;
+#[cfg(feature = "tui")]
+pub mod theme;
+#[cfg(feature = "gui")]
+mod tiles;
 #[cfg(feature = "tui")]
 pub mod tui;
+#[cfg(feature = "tui")]
+mod viewport;
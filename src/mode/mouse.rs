@@ -0,0 +1,87 @@
+//! Playing with the mouse, shared by the terminal and graphical interface.
+
+use mine::{Action, Field};
+
+/// A mouse button.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Button {
+    Left,
+    Middle,
+    Right,
+}
+
+/// What the mouse did: pressing or releasing a button, or moving.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MouseEvent {
+    Press(Button),
+    Release(Button),
+    Move,
+}
+
+/// Turns mouse events into actions on the field.
+///
+/// A left click reveals and a right click flags, both on release. A middle click reveals around
+/// the cell, as does pressing both the left and right button and releasing them. Moving the mouse
+/// moves the cursor along.
+#[derive(Default)]
+pub struct Mouse {
+    left: bool,
+    right: bool,
+    /// Whether both buttons have been held together since they were last all released.
+    chord: bool,
+}
+
+impl Mouse {
+    /// Returns the actions for the event at the cell under the mouse, in the order they are to be
+    /// applied. The cell is `None` if the mouse is not on the field.
+    pub fn actions(
+        &mut self,
+        event: MouseEvent,
+        cell: Option<(usize, usize)>,
+        field: &Field,
+    ) -> Vec<Action> {
+        let action = match event {
+            MouseEvent::Move => None,
+            MouseEvent::Press(Button::Middle) => Some(Action::RevealAround),
+            MouseEvent::Press(button) => {
+                match button {
+                    Button::Left => self.left = true,
+                    _ => self.right = true,
+                }
+                self.chord |= self.left && self.right;
+                None
+            }
+            MouseEvent::Release(Button::Middle) => None,
+            MouseEvent::Release(button) => {
+                match button {
+                    Button::Left => self.left = false,
+                    _ => self.right = false,
+                }
+                if !self.chord {
+                    Some(match button {
+                        Button::Left => Action::Reveal,
+                        _ => Action::Flag,
+                    })
+                } else if !self.left && !self.right {
+                    self.chord = false;
+                    Some(Action::RevealAround)
+                } else {
+                    None
+                }
+            }
+        };
+
+        // Events outside of the field only keep track of the buttons.
+        let (x, y) = match cell {
+            Some(position) => position,
+            None => return Vec::new(),
+        };
+
+        let mut actions = Vec::new();
+        if (x, y) != (field.cursor_pos_x(), field.cursor_pos_y()) {
+            actions.push(Action::CursorTo(x, y));
+        }
+        actions.extend(action);
+        actions
+    }
+}
@@ -0,0 +1,243 @@
+use super::keymap::{Command, KeyPress, Keymap};
+use super::keys::Keys;
+use super::screen::{Frame, Screen};
+use super::theme::Look;
+use super::tui::frame;
+use super::viewport::Viewport;
+use crate::Replay;
+use console::{style, truncate_str, Key, Term};
+use mine::{field::Field, replay::Replay as Recording};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// The slowest and fastest playback speeds.
+const MIN_SPEED: f64 = 1.0 / 16.0;
+const MAX_SPEED: f64 = 64.0;
+
+/// The number of steps skipped by seeking.
+const SEEK_STEPS: usize = 10;
+/// The number of terminal rows taken by other lines than the field: the state of the playback,
+/// the scroll indicators and the keys.
+const RESERVED_LINES: usize = 3;
+
+pub fn replay(opt: Replay) -> Result<(), io::Error> {
+    let r = Recording::read_from(&opt.file)?;
+
+    if opt.verify {
+        return match r.verify() {
+            Ok(()) => {
+                println!("ok: the game ends in the recorded state ({})", r.result);
+                Ok(())
+            }
+            Err(state) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "the game ends in state {}, but {} was recorded",
+                    state, r.result
+                ),
+            )),
+        };
+    }
+
+    let look = opt.look.look()?;
+    let keymap = Keymap::load()?;
+    let mut keys = Keys::new();
+    let mut screen = Screen::default();
+    let mut viewport = Viewport::default();
+
+    let term = Term::stdout();
+    term.set_title("mine replay");
+    term.hide_cursor()?;
+    // Open the alternative screen buffer.
+    print!("\u{1b}[?1049h");
+
+    let mut playback = Playback {
+        replay: &r,
+        look: &look,
+        field: r.field(),
+        position: 0,
+        time: Duration::ZERO,
+        speed: opt.speed.clamp(MIN_SPEED, MAX_SPEED),
+        paused: false,
+    };
+    let mut last_tick = Instant::now();
+
+    loop {
+        // Advance the playback by the time that has passed, and apply the steps that are due.
+        let now = Instant::now();
+        if !playback.paused {
+            playback.time += now.duration_since(last_tick).mul_f64(playback.speed);
+        }
+        last_tick = now;
+        while playback.position < r.steps.len() && r.steps[playback.position].at <= playback.time {
+            playback.step();
+        }
+
+        screen.draw(&term, playback.frame(&term, &keymap, &mut viewport))?;
+
+        // Wait for the next step to be due, or for a key to be pressed.
+        let timeout = match r.steps.get(playback.position) {
+            Some(step) if !playback.paused => {
+                Some((step.at - playback.time).div_f64(playback.speed))
+            }
+            _ => None,
+        };
+        let key = match keys.read_key(timeout)? {
+            Some(key) => key,
+            None => continue,
+        };
+
+        // The keys that play the game control the playback: the keys that act on a cell pause,
+        // and the keys that move the cursor step, seek and change the speed.
+        let command = KeyPress::from_console(&key).and_then(|press| keymap.command(&press));
+        match command {
+            // Pause and resume
+            Some(Command::Flag | Command::Reveal) => playback.paused = !playback.paused,
+
+            // Single steps
+            Some(Command::Right) => {
+                playback.paused = true;
+                playback.seek(playback.position + 1)
+            }
+            Some(Command::Left) => {
+                playback.paused = true;
+                playback.seek(playback.position.saturating_sub(1))
+            }
+
+            // Seeking
+            Some(Command::EdgeRight) => playback.seek(playback.position + SEEK_STEPS),
+            Some(Command::EdgeLeft) => playback.seek(playback.position.saturating_sub(SEEK_STEPS)),
+            Some(Command::EdgeUp) => playback.seek(0),
+            Some(Command::EdgeDown) => playback.seek(r.steps.len()),
+
+            // Speed
+            Some(Command::Up) => playback.speed = (playback.speed * 2.0).min(MAX_SPEED),
+            Some(Command::Down) => playback.speed = (playback.speed / 2.0).max(MIN_SPEED),
+
+            // Quit, also with `^C`
+            Some(Command::Quit) => break,
+            None if key == Key::CtrlC => break,
+
+            // Otherwise, do nothing
+            _ => {}
+        }
+    }
+
+    term.show_cursor()?;
+    // Close the alternative screen buffer again.
+    print!("\u{1b}[?1049l");
+
+    Ok(())
+}
+
+/// The state of the playback of a replay.
+struct Playback<'r> {
+    replay: &'r Recording,
+    look: &'r Look,
+    /// The field after the steps before `position` have been applied.
+    field: Field,
+    /// The number of steps that have been applied.
+    position: usize,
+    /// The time in the recording up to which the replay has been played.
+    time: Duration,
+    speed: f64,
+    paused: bool,
+}
+
+impl Playback<'_> {
+    /// Applies the next step.
+    fn step(&mut self) {
+        let step = self.replay.steps[self.position];
+        self.field.apply_action(step.action);
+        self.position += 1;
+        self.time = self.time.max(step.at);
+    }
+
+    /// Moves the playback to the position, such that the steps before it have been applied.
+    fn seek(&mut self, position: usize) {
+        let position = position.min(self.replay.steps.len());
+        if position < self.position {
+            // Steps cannot be taken back, so the field is played from the start again.
+            self.field = self.replay.field();
+            self.position = 0;
+        }
+        while self.position < position {
+            self.step();
+        }
+
+        self.time = match position {
+            0 => Duration::ZERO,
+            _ => self.replay.steps[position - 1].at,
+        };
+    }
+
+    /// Returns the frame to draw: the state of the playback above the field, and the keys that
+    /// control it below.
+    fn frame(&self, term: &Term, keymap: &Keymap, viewport: &mut Viewport) -> Frame {
+        let steps = self.replay.steps.len();
+        let state = if self.position == steps {
+            // The whole game has been played back. Check it against the recorded result.
+            let state = self.field.game_state();
+            if state == self.replay.result {
+                format!("ended: {}", state)
+            } else {
+                format!("ended: {}, but {} was recorded", state, self.replay.result)
+            }
+        } else if self.paused {
+            "paused".to_string()
+        } else {
+            "playing".to_string()
+        };
+
+        let status = style(format!(
+            "replay {}/{} — {} — {}x — seed {}",
+            self.position,
+            steps,
+            state,
+            self.speed,
+            self.field.seed()
+        ))
+        .color256(238)
+        .to_string();
+        let mut frame = frame(
+            term,
+            &self.field,
+            self.look,
+            None,
+            viewport,
+            &status,
+            RESERVED_LINES,
+        );
+
+        let help = help(keymap);
+        let help = truncate_str(&help, frame.size.1 as usize, "…");
+        frame.footer.push(style(help).italic().to_string());
+        frame
+    }
+}
+
+/// Returns the keys that control the playback, like `f pause — h/l step — q quit`. Controls
+/// that are not bound to a key are left out.
+fn help(keymap: &Keymap) -> String {
+    let mut controls = Vec::new();
+    if let Some(key) = keymap.key(Command::Flag).or(keymap.key(Command::Reveal)) {
+        controls.push(format!("{} pause", key));
+    }
+    for (commands, what) in [
+        (&[Command::Left, Command::Right][..], "step"),
+        (&[Command::EdgeLeft, Command::EdgeRight], "seek"),
+        (&[Command::EdgeUp, Command::EdgeDown], "start/end"),
+        (&[Command::Up, Command::Down], "faster/slower"),
+        (&[Command::Quit], "quit"),
+    ] {
+        let keys: Option<Vec<String>> = commands
+            .iter()
+            .map(|command| keymap.key(*command).map(|key| key.to_string()))
+            .collect();
+        if let Some(keys) = keys {
+            controls.push(format!("{} {}", keys.join("/"), what));
+        }
+    }
+
+    controls.join(" — ")
+}
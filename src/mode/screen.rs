@@ -0,0 +1,137 @@
+//! Differential rendering of the terminal interface.
+//!
+//! Clearing the terminal and printing the whole field after every key flickers, and is slow over
+//! a remote connection on large fields. The screen keeps the frame it drew last, and only repaints
+//! what changed in the next one: the status line, the cells that changed and the lines below the
+//! field. The cursor is moved with escape sequences, and a frame is written in a single write.
+
+use super::viewport::CELL_WIDTH;
+use console::{truncate_str, Term};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+/// What is shown in the terminal while playing.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Frame {
+    /// The size of the terminal in rows and columns, as returned by `Term::size`.
+    pub size: (u16, u16),
+    /// The line above the field.
+    pub status: String,
+    /// The styled cells of the field, row by row, as returned by `Styled::rows`.
+    pub cells: Vec<Vec<String>>,
+    /// The lines below the field.
+    pub footer: Vec<String>,
+}
+
+/// The terminal, as far as it has been drawn by frames.
+#[derive(Default)]
+pub struct Screen {
+    /// The frame on the screen, or `None` if the screen is to be drawn anew.
+    shown: Option<Frame>,
+}
+
+impl Screen {
+    /// Forgets what is on the screen, such that the next frame is drawn completely. This is needed
+    /// after something else has been written to the terminal.
+    pub fn invalidate(&mut self) {
+        self.shown = None;
+    }
+
+    /// Draws the frame. Only the differences with the frame on the screen are written, unless the
+    /// size of the terminal or of the field changed.
+    pub fn draw(&mut self, mut term: &Term, frame: Frame) -> io::Result<()> {
+        let mut out = String::new();
+        match &self.shown {
+            Some(shown) if shown.size == frame.size && shape(shown) == shape(&frame) => {
+                repaint(shown, &frame, &mut out)
+            }
+            _ => paint(&frame, &mut out),
+        }
+
+        term.write_all(out.as_bytes())?;
+        term.flush()?;
+        self.shown = Some(frame);
+        Ok(())
+    }
+
+    /// Draws the status line over that of the frame on the screen, keeping the rest of it. The
+    /// line is cut off at the width of the terminal, like that of a frame. Does nothing if the
+    /// screen is to be drawn anew, or if the size of the terminal changed since.
+    pub fn draw_status(&mut self, mut term: &Term, status: &str) -> io::Result<()> {
+        let shown = match &mut self.shown {
+            Some(shown) if shown.size == term.size() => shown,
+            _ => return Ok(()),
+        };
+        let status = truncate_str(status, shown.size.1 as usize, "…").to_string();
+        if shown.status == status {
+            return Ok(());
+        }
+
+        let mut out = String::new();
+        move_to(&mut out, 0, 0);
+        out.push_str(&status);
+        out.push_str(CLEAR_LINE);
+        shown.status = status;
+
+        term.write_all(out.as_bytes())?;
+        term.flush()
+    }
+}
+
+/// Returns the number of cells in every row of the field of the frame.
+fn shape(frame: &Frame) -> Vec<usize> {
+    frame.cells.iter().map(Vec::len).collect()
+}
+
+/// Writes the whole frame to a cleared screen.
+fn paint(frame: &Frame, out: &mut String) {
+    out.push_str("\u{1b}[2J");
+    move_to(out, 0, 0);
+    out.push_str(&frame.status);
+    for (y, row) in frame.cells.iter().enumerate() {
+        move_to(out, y + 1, 0);
+        out.extend(row.iter().map(String::as_str));
+    }
+    for (y, line) in frame.footer.iter().enumerate() {
+        move_to(out, frame.cells.len() + 1 + y, 0);
+        out.push_str(line);
+    }
+}
+
+/// Writes the lines and cells of the frame that differ from those of the frame on the screen,
+/// which is of the same shape.
+fn repaint(shown: &Frame, frame: &Frame, out: &mut String) {
+    if shown.status != frame.status {
+        move_to(out, 0, 0);
+        out.push_str(&frame.status);
+        out.push_str(CLEAR_LINE);
+    }
+
+    for (y, (shown_row, row)) in shown.cells.iter().zip(&frame.cells).enumerate() {
+        for (x, (shown_cell, cell)) in shown_row.iter().zip(row).enumerate() {
+            if shown_cell != cell {
+                move_to(out, y + 1, x * CELL_WIDTH);
+                out.push_str(cell);
+            }
+        }
+    }
+
+    // Lines that are no longer there are cleared.
+    for y in 0..shown.footer.len().max(frame.footer.len()) {
+        let line = frame.footer.get(y);
+        if shown.footer.get(y) != line {
+            move_to(out, frame.cells.len() + 1 + y, 0);
+            out.push_str(line.map_or("", String::as_str));
+            out.push_str(CLEAR_LINE);
+        }
+    }
+}
+
+/// Clears the rest of the line after the cursor.
+const CLEAR_LINE: &str = "\u{1b}[K";
+
+/// Moves the cursor to the zero-based row and column.
+fn move_to(out: &mut String, row: usize, column: usize) {
+    // Writing to a string does not fail.
+    let _ = write!(out, "\u{1b}[{};{}H", row + 1, column + 1);
+}
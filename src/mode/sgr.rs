@@ -0,0 +1,206 @@
+//! Mouse input in the terminal, through xterm's SGR mouse reporting.
+//!
+//! With reporting enabled, the terminal sends every press, release and movement of the mouse as an
+//! escape sequence like `ESC [ < 0 ; 12 ; 4 M`: the button and modifiers, the column and the row,
+//! and `M` for a press or movement or `m` for a release. The console crate does not know these
+//! sequences. It returns the first three characters after the escape as an unknown sequence, and
+//! the rest as separate characters.
+
+use super::mouse::{Button, MouseEvent};
+use super::viewport::{Viewport, CELL_WIDTH};
+use console::{Key, Term};
+use std::io;
+
+/// Turns on the reporting of presses, releases and all movement, in the SGR encoding.
+pub const ENABLE: &str = "\u{1b}[?1003h\u{1b}[?1006h";
+/// Turns the reporting off again.
+pub const DISABLE: &str = "\u{1b}[?1006l\u{1b}[?1003l";
+
+/// The terminal row the field starts on, below the status line.
+const FIELD_ROW: usize = 1;
+/// The longest report that is read, such that a garbled one does not block input.
+const MAX_REPORT_LEN: usize = 16;
+
+/// A mouse report, at a zero-based terminal column and row.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Report {
+    pub event: MouseEvent,
+    pub column: usize,
+    pub row: usize,
+}
+
+/// Returns whether the key is the start of a mouse report, as returned by `Term::read_key_raw`.
+pub fn is_report(key: &Key) -> bool {
+    matches!(key, Key::UnknownEscSeq(seq) if seq.len() == 3 && seq[..2] == ['[', '<'])
+}
+
+/// Reads the rest of a mouse report that started with `key`, see `is_report`.
+///
+/// Returns `None` for reports that are not used, like the scroll wheel, and for reports that
+/// cannot be parsed.
+pub fn read_report(term: &Term, key: &Key) -> io::Result<Option<Report>> {
+    let mut report = match key {
+        Key::UnknownEscSeq(seq) => seq[2..].iter().collect::<String>(),
+        _ => return Ok(None),
+    };
+
+    let pressed = loop {
+        if report.len() > MAX_REPORT_LEN {
+            return Ok(None);
+        }
+        match term.read_key_raw()? {
+            Key::Char('M') => break true,
+            Key::Char('m') => break false,
+            Key::Char(c) => report.push(c),
+            _ => return Ok(None),
+        }
+    };
+
+    Ok(parse_report(&report, pressed))
+}
+
+/// Parses the `button;column;row` part of a report.
+fn parse_report(report: &str, pressed: bool) -> Option<Report> {
+    let mut values = report.split(';').map(|value| value.parse::<usize>().ok());
+    let (code, column, row) = (values.next()??, values.next()??, values.next()??);
+    if values.next().is_some() || column == 0 || row == 0 {
+        return None;
+    }
+
+    // The lowest two bits hold the button. Movement adds 32, the scroll wheel 64, and the shift,
+    // meta and control keys 4, 8 and 16.
+    let event = if code & 64 != 0 {
+        return None;
+    } else if code & 32 != 0 {
+        MouseEvent::Move
+    } else {
+        let button = match code & 3 {
+            0 => Button::Left,
+            1 => Button::Middle,
+            2 => Button::Right,
+            _ => return None,
+        };
+        if pressed {
+            MouseEvent::Press(button)
+        } else {
+            MouseEvent::Release(button)
+        }
+    };
+
+    Some(Report {
+        event,
+        column: column - 1,
+        row: row - 1,
+    })
+}
+
+impl Report {
+    /// Returns the position of the cell under the mouse, if it is on the part of the field shown
+    /// in the viewport.
+    pub fn cell(&self, viewport: &Viewport) -> Option<(usize, usize)> {
+        let row = self.row.checked_sub(FIELD_ROW)?;
+        viewport.cell_at(self.column / CELL_WIDTH, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mine::Field;
+
+    fn report(event: MouseEvent, column: usize, row: usize) -> Option<Report> {
+        Some(Report { event, column, row })
+    }
+
+    #[test]
+    fn presses_and_releases() {
+        let left = Button::Left;
+        assert_eq!(
+            parse_report("0;12;4", true),
+            report(MouseEvent::Press(left), 11, 3)
+        );
+        assert_eq!(
+            parse_report("0;12;4", false),
+            report(MouseEvent::Release(left), 11, 3)
+        );
+        assert_eq!(
+            parse_report("1;1;1", true),
+            report(MouseEvent::Press(Button::Middle), 0, 0)
+        );
+        assert_eq!(
+            parse_report("2;1;1", false),
+            report(MouseEvent::Release(Button::Right), 0, 0)
+        );
+    }
+
+    #[test]
+    fn motion_and_modifiers() {
+        // Moving with no button held, and dragging with the left and right button.
+        for code in ["35", "32", "34"] {
+            let report = parse_report(&format!("{};5;6", code), true);
+            assert_eq!(
+                report,
+                Some(Report {
+                    event: MouseEvent::Move,
+                    column: 4,
+                    row: 5
+                })
+            );
+        }
+
+        // Shift, meta and control do not change the button.
+        for code in ["4", "8", "16", "28"] {
+            let report = parse_report(&format!("{};5;6", code), true).unwrap();
+            assert_eq!(report.event, MouseEvent::Press(Button::Left));
+        }
+        assert_eq!(
+            parse_report("36;5;6", true).unwrap().event,
+            MouseEvent::Move
+        );
+    }
+
+    #[test]
+    fn unused_and_malformed_reports() {
+        for report in [
+            // The scroll wheel, up and down.
+            "64;5;6",
+            "65;5;6",
+            // A release without a button, as sent by the older encodings.
+            "3;5;6",
+            "",
+            "0;5",
+            "0;5;6;7",
+            "0;;6",
+            "x;5;6",
+            "-1;5;6",
+            "0;0;6",
+            "0;5;0",
+            "0;99999999999999999999;6",
+        ] {
+            assert_eq!(parse_report(report, true), None, "{:?}", report);
+        }
+    }
+
+    #[test]
+    fn cells_under_the_mouse() {
+        let f = Field::from_rows(&["....", "....", "...*"]).unwrap();
+        let mut viewport = Viewport::default();
+        viewport.update((20, 80), &f, 2);
+        let at = |column, row| {
+            Report {
+                event: MouseEvent::Move,
+                column,
+                row,
+            }
+            .cell(&viewport)
+        };
+
+        assert_eq!(at(0, FIELD_ROW), Some((0, 0)));
+        assert_eq!(at(1, FIELD_ROW), Some((0, 0)));
+        assert_eq!(at(7, FIELD_ROW + 2), Some((3, 2)));
+        // The status line above the field, and the space right of it and below it.
+        assert_eq!(at(0, 0), None);
+        assert_eq!(at(8, FIELD_ROW), None);
+        assert_eq!(at(0, FIELD_ROW + 3), None);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::Stats;
+use mine::records::{records_path, Records};
+use std::io::{self, Write};
+
+pub fn stats(opt: Stats) -> Result<(), io::Error> {
+    let records = Records::load()?;
+    let mut out = io::stdout().lock();
+
+    if opt.csv {
+        return write_csv(&records, &mut out);
+    }
+
+    if records.games.is_empty() {
+        writeln!(
+            out,
+            "no games have been finished yet; they are recorded in {}",
+            records_path()?.display()
+        )?;
+        return Ok(());
+    }
+
+    for (i, record) in records.boards().iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", record.board)?;
+        writeln!(
+            out,
+            "  played {} — won {} ({:.0}%) — streak {} (best {}) — best time {}",
+            record.played,
+            record.won,
+            record.won as f64 / record.played as f64 * 100.0,
+            record.streak,
+            record.best_streak,
+            record.best_time.map_or("-".to_string(), |time| format!(
+                "{:.1}s",
+                time.as_secs_f64()
+            ))
+        )?;
+
+        for (place, game) in records
+            .leaderboard(record.board, opt.top)
+            .iter()
+            .enumerate()
+        {
+            writeln!(
+                out,
+                "  {:>2}. {:>7.1}s  {}  {:.2} 3BV/s",
+                place + 1,
+                game.stats.elapsed.as_secs_f64(),
+                game.day(),
+                game.stats.three_bv_per_second()
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes every recorded game as a line of comma separated values, after a header.
+fn write_csv(records: &Records, mut w: impl Write) -> io::Result<()> {
+    writeln!(
+        w,
+        "date,difficulty,width,height,mines,result,time,3bv,solved_3bv,3bv_per_second,left_clicks,right_clicks,chord_clicks,efficiency"
+    )?;
+    for game in &records.games {
+        writeln!(
+            w,
+            "{},{},{},{},{},{},{:.3},{},{},{:.3},{},{},{},{:.3}",
+            game.day(),
+            game.board.difficulty(),
+            game.board.width,
+            game.board.height,
+            game.board.mines,
+            if game.won { "won" } else { "lost" },
+            game.stats.elapsed.as_secs_f64(),
+            game.stats.three_bv,
+            game.stats.solved_three_bv,
+            game.stats.three_bv_per_second(),
+            game.stats.clicks.left,
+            game.stats.clicks.right,
+            game.stats.clicks.chord,
+            game.stats.efficiency()
+        )?;
+    }
+
+    Ok(())
+}
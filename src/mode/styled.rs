@@ -0,0 +1,93 @@
+//! Terminal rendering of a field, styled with colors by the console crate.
+
+use super::theme::Look;
+use super::viewport::Viewport;
+use mine::{cell::CellState, view::CellView, Field};
+use std::fmt;
+
+/// Displays a field in the terminal in the colors and glyphs of a look, optionally with a heatmap
+/// of the probability that each hidden cell is a mine, and optionally only the part of it in a
+/// viewport.
+pub struct Styled<'f> {
+    field: &'f Field,
+    look: &'f Look,
+    probabilities: Option<&'f [f64]>,
+    viewport: Option<Viewport>,
+}
+
+impl<'f> Styled<'f> {
+    /// Displays the field without a heatmap.
+    pub fn new(field: &'f Field, look: &'f Look) -> Self {
+        Self {
+            field,
+            look,
+            probabilities: None,
+            viewport: None,
+        }
+    }
+
+    /// Displays the field with a heatmap of the probabilities, as computed by
+    /// `probability::mine_probabilities`.
+    pub fn with_heatmap(field: &'f Field, look: &'f Look, probabilities: &'f [f64]) -> Self {
+        Self {
+            field,
+            look,
+            probabilities: Some(probabilities),
+            viewport: None,
+        }
+    }
+
+    /// Displays only the cells in the viewport, instead of the whole field.
+    pub fn in_viewport(self, viewport: Viewport) -> Self {
+        Self {
+            viewport: Some(viewport),
+            ..self
+        }
+    }
+}
+
+impl Styled<'_> {
+    /// Returns the styled cells, row by row, as they are written to the terminal.
+    pub fn rows(&self) -> Vec<Vec<String>> {
+        let (columns, rows) = match self.viewport {
+            Some(viewport) => (viewport.columns(), viewport.rows()),
+            None => (0..self.field.width(), 0..self.field.height()),
+        };
+        rows.map(|y| {
+            columns
+                .clone()
+                .map(|x| style_cell(&self.field.cell_view(x, y, self.probabilities), self.look))
+                .collect()
+        })
+        .collect()
+    }
+}
+
+impl fmt::Display for Styled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows: Vec<String> = self.rows().into_iter().map(|row| row.concat()).collect();
+
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+/// Returns the cell as it is shown in the terminal: two columns with a styled glyph.
+fn style_cell(view: &CellView, look: &Look) -> String {
+    let theme = &look.theme;
+    let (state, mut style) = match (view.state, view.under_flag) {
+        // A flag that has been revealed shows what was under it, in reverse.
+        (CellState::Flagged, Some(under)) => (under, theme.cell(under).reverse()),
+        (state, _) => (state, theme.cell(state)),
+    };
+
+    // The cursor goes over a highlight, which goes over the heatmap.
+    if view.cursor {
+        style = theme.cursor(style);
+    } else if view.highlight {
+        style = theme.highlight(style);
+    } else if let Some(probability) = view.heat {
+        style = theme.heat(style, probability);
+    }
+
+    style.apply_to(look.glyphs.cell(state)).to_string()
+}
@@ -0,0 +1,381 @@
+//! Colors and glyphs of the field in the terminal.
+//!
+//! A theme holds the colors of the field, from the 256 colors of the terminal. Besides the
+//! built-in themes, a theme can be read from a file of `key = value` lines, where every value is
+//! a color number, or `none` to leave the terminal's own color:
+//!
+//! ```text
+//! # Revealed cells without neighbouring mines, numbers from 1 to 8, then the colors of mines,
+//! # flags, the cursor and hint highlights.
+//! base = light
+//! empty = 252
+//! numbers = 21 28 160 18 88 30 16 244
+//! mine = 160
+//! flag = 160
+//! cursor = 220
+//! highlight = 210
+//! heat = 28 34 70 106 142 178 214 208 202 196 160
+//! ```
+//!
+//! Keys that are left out are taken from the `base` theme, or from the dark theme. The cursor and
+//! highlight colors are background colors. Without them, the cursor is shown in reverse and
+//! highlights are underlined. The heat colors are the backgrounds of the heatmap, from safe to
+//! certain mines; without any, there is no heatmap.
+//!
+//! When the `NO_COLOR` environment variable is set and no theme is given, the plain theme is used
+//! and the rest of the interface is not colored either.
+
+use super::keymap;
+use console::Style;
+use mine::cell::CellState;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+/// The names of the built-in themes.
+const THEMES: [&str; 5] = ["dark", "light", "classic", "colorblind", "plain"];
+
+// The options that change the look of the field. Not a doc comment, like that of `GameConfig`,
+// because structopt would use it as the description of the subcommands.
+#[derive(StructOpt)]
+pub struct LookConfig {
+    /// The colors of the field: dark, light, classic, colorblind, plain, or the path of a theme
+    /// file. Defaults to dark, or to plain if the NO_COLOR environment variable is set.
+    #[structopt(long)]
+    theme: Option<String>,
+
+    /// The characters of hidden cells, flags and mines: ascii, unicode or emoji.
+    #[structopt(long, default_value = "ascii")]
+    glyphs: Glyphs,
+}
+
+impl LookConfig {
+    /// Returns the look chosen by the options.
+    pub fn look(&self) -> io::Result<Look> {
+        let theme = match &self.theme {
+            Some(theme) => Theme::load(theme)?,
+            None if no_color() => {
+                console::set_colors_enabled(false);
+                Theme::plain()
+            }
+            None => Theme::dark(),
+        };
+
+        Ok(Look {
+            theme,
+            glyphs: self.glyphs,
+        })
+    }
+}
+
+/// Returns whether the `NO_COLOR` environment variable asks for output without colors.
+fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// How the field is drawn: its colors and the characters of its cells.
+pub struct Look {
+    pub theme: Theme,
+    pub glyphs: Glyphs,
+}
+
+/// The colors of the field, as numbers of the 256 terminal colors. `None` leaves the color of the
+/// terminal.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Theme {
+    /// The color of revealed cells without neighbouring mines.
+    empty: Option<u8>,
+    /// The colors of the numbers of neighbouring mines, from 1 to 8.
+    numbers: [Option<u8>; 8],
+    mine: Option<u8>,
+    flag: Option<u8>,
+    /// The background of the cell under the cursor.
+    cursor: Option<u8>,
+    /// The background of a mine highlighted by a hint.
+    highlight: Option<u8>,
+    /// The backgrounds of the heatmap, from safe cells to certain mines.
+    heat: Vec<u8>,
+}
+
+impl Theme {
+    /// The colors that the tui has always had, for terminals with a dark background.
+    pub fn dark() -> Self {
+        Self {
+            empty: Some(0),
+            numbers: [30, 60, 90, 120, 150, 180, 210, 240].map(Some),
+            mine: Some(1),
+            flag: Some(1),
+            cursor: Some(3),
+            highlight: Some(1),
+            heat: vec![28, 34, 70, 106, 142, 178, 214, 208, 202, 196, 160],
+        }
+    }
+
+    /// Darker colors that can be read on a light background.
+    pub fn light() -> Self {
+        Self {
+            empty: Some(252),
+            numbers: [21, 28, 160, 18, 88, 30, 16, 244].map(Some),
+            mine: Some(160),
+            flag: Some(160),
+            cursor: Some(220),
+            highlight: Some(210),
+            ..Self::dark()
+        }
+    }
+
+    /// The colors of the numbers in the minesweeper of old Windows versions.
+    pub fn classic() -> Self {
+        Self {
+            empty: Some(250),
+            numbers: [21, 28, 196, 18, 88, 30, 16, 244].map(Some),
+            mine: Some(16),
+            flag: Some(196),
+            cursor: Some(250),
+            highlight: Some(196),
+            ..Self::dark()
+        }
+    }
+
+    /// Colors that can be told apart with the common kinds of color blindness, after the palette
+    /// of Okabe and Ito, with a heatmap from blue to yellow instead of from green to red.
+    pub fn colorblind() -> Self {
+        Self {
+            empty: Some(238),
+            numbers: [32, 214, 117, 36, 227, 166, 175, 250].map(Some),
+            mine: Some(166),
+            flag: Some(214),
+            cursor: Some(227),
+            highlight: Some(175),
+            heat: vec![17, 18, 24, 25, 31, 67, 102, 137, 172, 178, 220],
+        }
+    }
+
+    /// No colors at all. The cursor is shown in reverse and highlights are underlined.
+    pub fn plain() -> Self {
+        Self {
+            empty: None,
+            numbers: [None; 8],
+            mine: None,
+            flag: None,
+            cursor: None,
+            highlight: None,
+            heat: Vec::new(),
+        }
+    }
+
+    /// Returns the built-in theme with the name.
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "classic" => Some(Self::classic()),
+            "colorblind" => Some(Self::colorblind()),
+            "plain" => Some(Self::plain()),
+            _ => None,
+        }
+    }
+
+    /// Returns the built-in theme with the name, or reads the theme from the file at that path.
+    pub fn load(theme: &str) -> io::Result<Self> {
+        if let Some(theme) = Self::named(theme) {
+            return Ok(theme);
+        }
+
+        let path = Path::new(theme);
+        if !path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "no theme '{}', expected {} or the path of a theme file",
+                    theme,
+                    THEMES.join(", ")
+                ),
+            ));
+        }
+        fs::read_to_string(path)?.parse().map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid theme {}: {}", path.display(), err),
+            )
+        })
+    }
+
+    /// Returns whether the theme has a heatmap.
+    pub fn has_heat(&self) -> bool {
+        !self.heat.is_empty()
+    }
+
+    /// Returns the style of a cell, which is a revealed cell or a flag that is not revealed.
+    pub fn cell(&self, state: CellState) -> Style {
+        match state {
+            CellState::RevealedMine => color(self.base().bold(), self.mine),
+            CellState::Neighbours(0) => color(self.base(), self.empty),
+            CellState::Neighbours(n) => color(self.base(), self.numbers[n - 1]),
+            CellState::Flagged => color(self.base().reverse().bold().italic(), self.flag),
+            CellState::Hidden => self.base(),
+        }
+    }
+
+    /// Returns the style with the background of the cursor.
+    pub fn cursor(&self, style: Style) -> Style {
+        match self.cursor {
+            Some(cursor) => style.on_color256(cursor),
+            // A flag is already reversed, so reversing it again would hide the cursor.
+            None => style.underlined().reverse(),
+        }
+    }
+
+    /// Returns the style with the background of a mine highlighted by a hint.
+    pub fn highlight(&self, style: Style) -> Style {
+        match self.highlight {
+            Some(highlight) => style.on_color256(highlight),
+            None => style.underlined(),
+        }
+    }
+
+    /// Returns the style with the heatmap background of a hidden cell, if the theme has a heatmap.
+    pub fn heat(&self, style: Style, probability: f64) -> Style {
+        if self.heat.is_empty() {
+            return style;
+        }
+        let i = (probability.clamp(0.0, 1.0) * (self.heat.len() - 1) as f64).round() as usize;
+        style.black().on_color256(self.heat[i])
+    }
+
+    /// Returns the style that others start from. Without any colors, the styles are forced, such
+    /// that the cursor is shown even when `NO_COLOR` turned the colors of the terminal off.
+    fn base(&self) -> Style {
+        if *self == Self::plain() {
+            Style::new().force_styling(true)
+        } else {
+            Style::new()
+        }
+    }
+}
+
+fn color(style: Style, color: Option<u8>) -> Style {
+    match color {
+        Some(color) => style.color256(color),
+        None => style,
+    }
+}
+
+/// Parses a theme file, see the module documentation.
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Themes take the syntax of the configuration file, without its sections.
+        let entries = keymap::entries(s)?;
+        if let Some(entry) = entries.iter().find(|entry| !entry.section.is_empty()) {
+            return Err(format!("line {}: themes have no sections", entry.line));
+        }
+
+        let mut theme = match entries.iter().find(|entry| entry.name == "base") {
+            Some(base) => Self::named(base.value).ok_or_else(|| {
+                format!(
+                    "line {}: no theme '{}' to base on, expected {}",
+                    base.line,
+                    base.value,
+                    THEMES.join(", ")
+                )
+            })?,
+            None => Self::dark(),
+        };
+
+        for entry in entries.iter().filter(|entry| entry.name != "base") {
+            let (number, key, value) = (entry.line, entry.name, entry.value);
+            let colors = value
+                .split_whitespace()
+                .map(parse_color)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| format!("line {}: {}", number, err))?;
+            let single = || match colors[..] {
+                [color] => Ok(color),
+                _ => Err(format!(
+                    "line {}: expected a single color for {}",
+                    number, key
+                )),
+            };
+
+            match key {
+                "numbers" => {
+                    theme.numbers = colors.try_into().map_err(|_| {
+                        format!("line {}: expected 8 colors for the numbers", number)
+                    })?
+                }
+                "empty" => theme.empty = single()?,
+                "mine" => theme.mine = single()?,
+                "flag" => theme.flag = single()?,
+                "cursor" => theme.cursor = single()?,
+                "highlight" => theme.highlight = single()?,
+                "heat" => theme.heat = colors.into_iter().flatten().collect(),
+                _ => return Err(format!("line {}: unknown key '{}'", number, key)),
+            }
+        }
+
+        Ok(theme)
+    }
+}
+
+/// Parses a color number from 0 to 255, or `none`.
+fn parse_color(s: &str) -> Result<Option<u8>, String> {
+    match s {
+        "none" => Ok(None),
+        _ => s
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid color '{}', expected 0 to 255 or none", s)),
+    }
+}
+
+/// The characters that cells are drawn with. Every cell takes two columns of the terminal.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Glyphs {
+    /// `.`, `F` and `M`, which every terminal can show.
+    Ascii,
+    /// `■`, `⚑` and `✹`.
+    Unicode,
+    /// Emoji, which take both columns of a cell.
+    Emoji,
+}
+
+impl Glyphs {
+    /// Returns the two columns that show the cell. Revealed flags show what was under them.
+    pub fn cell(&self, state: CellState) -> String {
+        match (self, state) {
+            (_, CellState::Neighbours(n)) if n > 0 => format!(" {}", n),
+            (Glyphs::Ascii, CellState::Neighbours(_)) => " 0".to_string(),
+            (_, CellState::Neighbours(_)) => "  ".to_string(),
+            (Glyphs::Ascii, CellState::Hidden) => " .".to_string(),
+            (Glyphs::Ascii, CellState::Flagged) => " F".to_string(),
+            (Glyphs::Ascii, CellState::RevealedMine) => " M".to_string(),
+            (Glyphs::Unicode, CellState::Hidden) => " ■".to_string(),
+            (Glyphs::Unicode, CellState::Flagged) => " ⚑".to_string(),
+            (Glyphs::Unicode, CellState::RevealedMine) => " ✹".to_string(),
+            (Glyphs::Emoji, CellState::Hidden) => "🟩".to_string(),
+            (Glyphs::Emoji, CellState::Flagged) => "🚩".to_string(),
+            (Glyphs::Emoji, CellState::RevealedMine) => "💣".to_string(),
+        }
+    }
+}
+
+impl FromStr for Glyphs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(Glyphs::Ascii),
+            "unicode" => Ok(Glyphs::Unicode),
+            "emoji" => Ok(Glyphs::Emoji),
+            _ => Err(format!(
+                "invalid glyphs '{}', expected ascii, unicode or emoji",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Drawing of the field as tiles, for the gui.
+//!
+//! Every cell is drawn as a square tile of `TILE` pixels. Hidden cells are raised, bevelled tiles,
+//! revealed cells are flat with a grid line along their top and left edges. Numbers, flags and
+//! mines are drawn from the small bitmaps below, where every character is a pixel: `.` is left
+//! transparent, and other characters are looked up in the palette of the bitmap.
+
+use mine::{cell::CellState, view::CellView, Field};
+
+/// The size of a tile in pixels, both in width and height.
+pub const TILE: usize = 16;
+
+/// The width of the bevel of hidden tiles, and of the cursor outline.
+pub const BORDER: usize = 2;
+
+/// An RGBA color.
+pub type Color = [u8; 4];
+
+pub const HIDDEN: Color = [189, 189, 189, 255];
+pub const LIGHT: Color = [255, 255, 255, 255];
+pub const SHADOW: Color = [123, 123, 123, 255];
+const REVEALED: Color = [210, 210, 210, 255];
+pub const BLACK: Color = [20, 20, 20, 255];
+const RED: Color = [214, 22, 63, 255];
+const CURSOR: Color = [231, 185, 3, 255];
+/// The outline of a mine highlighted by a hint.
+const HIGHLIGHT: Color = [255, 120, 0, 255];
+
+/// The colors of the numbers of neighbouring mines, from 1 to 8.
+const NUMBER_COLORS: [Color; 8] = [
+    [0, 0, 255, 255],
+    [15, 112, 1, 255],
+    [251, 0, 6, 255],
+    [0, 0, 109, 255],
+    [107, 0, 2, 255],
+    [14, 110, 108, 255],
+    [30, 30, 30, 255],
+    [109, 109, 109, 255],
+];
+
+/// The digits 0 to 9, which are drawn at twice their size.
+#[rustfmt::skip]
+pub const DIGITS: [[&str; 7]; 10] = [
+    [
+        ".###.",
+        "#...#",
+        "#..##",
+        "#.#.#",
+        "##..#",
+        "#...#",
+        ".###.",
+    ],
+    [
+        "..#..",
+        ".##..",
+        "..#..",
+        "..#..",
+        "..#..",
+        "..#..",
+        ".###.",
+    ],
+    [
+        ".###.",
+        "#...#",
+        "....#",
+        "...#.",
+        "..#..",
+        ".#...",
+        "#####",
+    ],
+    [
+        "####.",
+        "....#",
+        "....#",
+        ".###.",
+        "....#",
+        "....#",
+        "####.",
+    ],
+    [
+        "...#.",
+        "..##.",
+        ".#.#.",
+        "#..#.",
+        "#####",
+        "...#.",
+        "...#.",
+    ],
+    [
+        "#####",
+        "#....",
+        "####.",
+        "....#",
+        "....#",
+        "#...#",
+        ".###.",
+    ],
+    [
+        ".###.",
+        "#....",
+        "#....",
+        "####.",
+        "#...#",
+        "#...#",
+        ".###.",
+    ],
+    [
+        "#####",
+        "....#",
+        "...#.",
+        "..#..",
+        ".#...",
+        ".#...",
+        ".#...",
+    ],
+    [
+        ".###.",
+        "#...#",
+        "#...#",
+        ".###.",
+        "#...#",
+        "#...#",
+        ".###.",
+    ],
+    [
+        ".###.",
+        "#...#",
+        "#...#",
+        ".####",
+        "....#",
+        "....#",
+        ".###.",
+    ],
+];
+/// A minus sign, in the size of the digits.
+pub const MINUS: [&str; 7] = [
+    ".....", ".....", ".....", "#####", ".....", ".....", ".....",
+];
+pub const DIGIT_SCALE: usize = 2;
+
+const MINE: [&str; 11] = [
+    ".....#.....",
+    "..#.###.#..",
+    "...#####...",
+    "..#######..",
+    "..#ww####..",
+    "###ww######",
+    "..#######..",
+    "..#######..",
+    "...#####...",
+    "..#.###.#..",
+    ".....#.....",
+];
+const MINE_PALETTE: &[(char, Color)] = &[('#', BLACK), ('w', LIGHT)];
+
+const FLAG: [&str; 10] = [
+    "....rr#....",
+    "..rrrr#....",
+    "rrrrrr#....",
+    "..rrrr#....",
+    "....rr#....",
+    "......#....",
+    "......#....",
+    "......#....",
+    "....#####..",
+    "..#########",
+];
+const FLAG_PALETTE: &[(char, Color)] = &[('#', BLACK), ('r', RED)];
+
+/// Draws the field to the canvas, with its top left corner at `origin`. The field takes `TILE`
+/// pixels for every cell in width and height. If mine probabilities are given, hidden cells are
+/// drawn as a heatmap running from green for safe cells to red for certain mines.
+pub fn draw(
+    field: &Field,
+    probabilities: Option<&[f64]>,
+    canvas: &mut Canvas,
+    origin: (usize, usize),
+) {
+    for view in field.view(probabilities) {
+        draw_tile(canvas, &view, origin);
+    }
+}
+
+/// Draws a single cell, with its outlines.
+fn draw_tile(canvas: &mut Canvas, view: &CellView, origin: (usize, usize)) {
+    let x = origin.0 + view.position.0 * TILE;
+    let y = origin.1 + view.position.1 * TILE;
+
+    match (view.state, view.under_flag) {
+        (CellState::Hidden, _) => {
+            let face = view.heat.map_or(HIDDEN, heat_color);
+            raised(canvas, x, y, TILE, face);
+        }
+        // A flag that was right, shown when the field is revealed.
+        (CellState::Flagged, Some(CellState::RevealedMine)) | (CellState::Flagged, None) => {
+            raised(canvas, x, y, TILE, HIDDEN);
+            canvas.centered(x, y, TILE, &FLAG, 1, FLAG_PALETTE);
+        }
+        // A flag that was wrong is crossed out.
+        (CellState::Flagged, Some(_)) => {
+            flat(canvas, x, y);
+            canvas.centered(x, y, TILE, &FLAG, 1, FLAG_PALETTE);
+            canvas.cross(x, y, RED);
+        }
+        (CellState::RevealedMine, _) => {
+            flat(canvas, x, y);
+            canvas.centered(x, y, TILE, &MINE, 1, MINE_PALETTE);
+        }
+        (CellState::Neighbours(0), _) => flat(canvas, x, y),
+        (CellState::Neighbours(n), _) => {
+            flat(canvas, x, y);
+            let palette = [('#', NUMBER_COLORS[n - 1])];
+            canvas.centered(x, y, TILE, &DIGITS[n], DIGIT_SCALE, &palette);
+        }
+    }
+
+    // The highlight is drawn inside of the cursor outline, such that both can be seen on the same
+    // cell.
+    if view.highlight {
+        canvas.outline(x + BORDER, y + BORDER, TILE - 2 * BORDER, HIGHLIGHT);
+    }
+    if view.cursor {
+        canvas.outline(x, y, TILE, CURSOR);
+    }
+}
+
+/// Draws a raised square of `size` pixels with a bevel, lit from the top left.
+pub fn raised(canvas: &mut Canvas, x: usize, y: usize, size: usize, face: Color) {
+    canvas.fill(x, y, size, size, SHADOW);
+    canvas.fill(x, y, size - BORDER, size - BORDER, LIGHT);
+    canvas.fill(
+        x + BORDER,
+        y + BORDER,
+        size - 2 * BORDER,
+        size - 2 * BORDER,
+        face,
+    );
+}
+
+/// Draws a flat, revealed tile with a grid line along its top and left edges.
+fn flat(canvas: &mut Canvas, x: usize, y: usize) {
+    canvas.fill(x, y, TILE, TILE, SHADOW);
+    canvas.fill(x + 1, y + 1, TILE - 1, TILE - 1, REVEALED);
+}
+
+/// Returns the heatmap color of a hidden cell, from green for safe cells to red for certain mines.
+fn heat_color(probability: f64) -> Color {
+    let p = probability.clamp(0.0, 1.0);
+    [
+        (220.0 * p) as u8 + 20,
+        (180.0 * (1.0 - p)) as u8 + 20,
+        20,
+        255,
+    ]
+}
+
+/// A frame of RGBA pixels, row by row. Drawing outside of the frame is ignored.
+pub struct Canvas<'f> {
+    frame: &'f mut [u8],
+    /// The width of the frame in pixels.
+    width: usize,
+}
+
+impl<'f> Canvas<'f> {
+    /// Returns a canvas on the frame, which is `width` pixels wide.
+    pub fn new(frame: &'f mut [u8], width: usize) -> Self {
+        Self { frame, width }
+    }
+
+    fn set(&mut self, x: usize, y: usize, color: Color) {
+        if x >= self.width {
+            return;
+        }
+        let i = (y * self.width + x) * 4;
+        if let Some(pixel) = self.frame.get_mut(i..i + 4) {
+            pixel.copy_from_slice(&color);
+        }
+    }
+
+    pub fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        for y in y..y + height {
+            for x in x..x + width {
+                self.set(x, y, color);
+            }
+        }
+    }
+
+    /// Draws an outline of `BORDER` pixels along the inside of the square.
+    fn outline(&mut self, x: usize, y: usize, size: usize, color: Color) {
+        self.fill(x, y, size, BORDER, color);
+        self.fill(x, y + size - BORDER, size, BORDER, color);
+        self.fill(x, y, BORDER, size, color);
+        self.fill(x + size - BORDER, y, BORDER, size, color);
+    }
+
+    /// Draws both diagonals of the tile.
+    fn cross(&mut self, x: usize, y: usize, color: Color) {
+        for i in BORDER..TILE - BORDER {
+            self.set(x + i, y + i, color);
+            self.set(x + TILE - 1 - i, y + i, color);
+        }
+    }
+
+    /// Draws the bitmap centered on a square of `size` pixels.
+    pub fn centered(
+        &mut self,
+        x: usize,
+        y: usize,
+        size: usize,
+        bitmap: &[&str],
+        scale: usize,
+        palette: &[(char, Color)],
+    ) {
+        let width = bitmap[0].len() * scale;
+        let height = bitmap.len() * scale;
+        let x = x + size.saturating_sub(width) / 2;
+        let y = y + size.saturating_sub(height) / 2;
+        self.bitmap(x, y, bitmap, scale, palette);
+    }
+
+    /// Draws the bitmap with its top left corner at the position, every pixel of it as a square of
+    /// `scale` pixels.
+    pub fn bitmap(
+        &mut self,
+        x: usize,
+        y: usize,
+        bitmap: &[&str],
+        scale: usize,
+        palette: &[(char, Color)],
+    ) {
+        for (row, line) in bitmap.iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                if let Some((_, color)) = palette.iter().find(|(p, _)| *p == c) {
+                    self.fill(x + column * scale, y + row * scale, scale, scale, *color);
+                }
+            }
+        }
+    }
+}
@@ -1,141 +1,524 @@
+use super::input::{Input, InputConfig, InputEvent};
+use super::keymap::{Command, KeyPress, Keymap};
+use super::keys::Keys;
+use super::screen::{Frame, Screen};
+use super::sgr;
+use super::styled::Styled;
+use super::theme::Look;
+use super::viewport::Viewport;
 use crate::{
-    field::{Action, Field, GameState},
+    config::{game_with_size, new_game, no_guess_note, parse_size, same_game},
+    session::Session,
     Tui,
 };
-use console::{style, Key, Term};
-use std::io::Write;
+use console::{style, truncate_str, Key, Term};
+use mine::{
+    field::{Action, Event, Field, GameState},
+    probability::mine_probabilities,
+    replay::{apply, Recorder},
+    stats::Stats,
+};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// The number of terminal rows taken by other lines than the field while playing: the status line
+/// and the scroll indicators.
+const GAME_LINES: usize = 2;
+/// The number of terminal rows taken by other lines than the field once the game has ended: the
+/// header, the scroll indicators, the summary, an error recording the game, the session, the keys
+/// to press and the prompt for new settings.
+const END_LINES: usize = 13;
+/// How often the clock in the status line is redrawn while it runs, as often as the time it shows
+/// changes.
+const TICK: Duration = Duration::from_millis(100);
+
+/// The game to play after the current one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Next {
+    /// The same field again, from the same seed.
+    Replay,
+    /// A new field with the same settings.
+    New,
+    /// A new field with settings asked from the player.
+    Settings,
+}
 
 pub fn tui(opt: Tui) -> Result<(), std::io::Error> {
-    // Initialize some sensible default values.
-    let width = opt.width;
-    let height = opt.height;
-    let mines = opt.mines;
-    let mut f = Field::new(height, width, mines);
+    let look = opt.look.look()?;
+    let keymap = Keymap::load()?;
+    let opt = opt.game;
+    let mut f = opt.field()?;
 
-    let mut term = Term::stdout();
-    term.set_title("mine");
-    term.hide_cursor()?;
-    // Close the alternative screen buffer again.
-    print!("\u{1b}[?1049h");
-    term.clear_screen()?;
-    term.write_fmt(format_args!("\n{}", f))?;
+    let mut message = None;
+    let mut heatmap = false;
+    // The mine probabilities shown by the heatmap, once computed. They are computed again only
+    // after cells are revealed or flagged, or for a new game.
+    let mut heat: Option<Option<Vec<f64>>> = None;
+    let mut recorder = opt.record.as_ref().map(|_| Recorder::new(&f));
+    let mut input = Input::new(InputConfig::load()?);
+    let mut session = Session::default();
+    let mut viewport = Viewport::default();
+    let mut screen = Screen::default();
+    let mut keys = Keys::new();
+    // Whether the status line shows a message, which the clock does not replace until the next key.
+    let mut showing_message = false;
 
-    let mut previous_input = Key::Unknown;
+    let term = Term::stdout();
+    term.set_title("mine");
+    let restore = Restore::setup(&term)?;
+    let status = status_line(&f);
+    screen.draw(
+        &term,
+        frame(&term, &f, &look, None, &mut viewport, &status, GAME_LINES),
+    )?;
 
     'gameloop: loop {
-        let input = term.read_key()?;
-
-        match input {
-            // Basic movement
-            Key::Char('h') | Key::ArrowLeft => {
-                // h — <
-                f.apply_action(Action::CursorLeft)
-            }
-            Key::Char('j') | Key::ArrowDown => {
-                // j — v
-                f.apply_action(Action::CursorDown)
-            }
-            Key::Char('k') | Key::ArrowUp => {
-                // k — ^
-                f.apply_action(Action::CursorUp)
-            }
-            Key::Char('l') | Key::ArrowRight => {
-                // l — >
-                f.apply_action(Action::CursorRight)
+        let timeout = (f.is_timing() && !showing_message).then(|| until_tick(&f));
+        let key = match keys.read_key(timeout)? {
+            Some(key) => key,
+            None => {
+                screen.draw_status(&term, &status_line(&f))?;
+                continue;
             }
+        };
+        let now = Instant::now();
 
-            // Movements to edges
-            Key::Char('H') | Key::Char('0') => {
-                // H — <<
-                f.apply_action(Action::CursorToEdgeLeft)
-            }
-            Key::Char('L') | Key::Char('$') => {
-                // L — >>
-                f.apply_action(Action::CursorToEdgeRight)
+        let command = KeyPress::from_console(&key).and_then(|press| keymap.command(&press));
+        match command {
+            // Toggle the heatmap of mine probabilities
+            Some(Command::Heatmap) => {
+                if look.theme.has_heat() {
+                    heatmap = !heatmap
+                } else {
+                    message = Some("no heatmap: the theme has no colors for it".to_string());
+                }
             }
-            Key::Char('G') => {
-                // G — vv
-                f.apply_action(Action::CursorToEdgeDown)
+
+            // Scroll by half a page or a whole page, down and up
+            Some(
+                command @ (Command::HalfPageDown
+                | Command::HalfPageUp
+                | Command::PageDown
+                | Command::PageUp),
+            ) => {
+                let page = viewport.height() as isize;
+                let rows = match command {
+                    Command::HalfPageDown => (page / 2).max(1),
+                    Command::HalfPageUp => -(page / 2).max(1),
+                    Command::PageDown => page,
+                    _ => -page,
+                };
+                let (x, y) = viewport.scroll(rows, &f);
+                if (x, y) != (f.cursor_pos_x(), f.cursor_pos_y()) {
+                    apply(&mut f, &mut recorder, Action::CursorTo(x, y));
+                }
             }
-            Key::Char('g') => {
-                // g — ^^
-                f.apply_action(Action::CursorToEdgeUp)
+
+            // Save
+            Some(Command::Save) => {
+                let save_path = opt.save_path(&f);
+                message = Some(match f.save_to(&save_path) {
+                    Ok(()) => format!("saved to {}", save_path.display()),
+                    Err(err) => format!("could not save: {}", err),
+                });
             }
 
-            // Flag selected cell
-            Key::Char('f') | Key::Char(' ') => {
-                // f — SPACE
-                f.apply_action(Action::Flag)
+            // New game, with the same or with new settings
+            Some(command @ (Command::NewGame | Command::NewSettings)) => {
+                let next = if command == Command::NewGame {
+                    Next::New
+                } else {
+                    Next::Settings
+                };
+                if recorder.is_some() {
+                    message = Some("cannot start a new game while recording".to_string());
+                } else {
+                    match next_game(&term, &f, next) {
+                        Ok(Some(new)) => {
+                            f = new;
+                            heat = None;
+                        }
+                        Ok(None) => {}
+                        Err(err) => message = Some(err),
+                    }
+                }
+                input.interrupt();
+                print!("{}", sgr::ENABLE);
+                // The prompt for new settings was written over the screen.
+                screen.invalidate();
             }
 
-            // Reveal selected cell
-            Key::Char('r') | Key::Enter | Key::Tab => {
-                // r — RETURN — TAB
-                f.apply_action(Action::Reveal);
+            // Quit, the game is saved such that it can be resumed. `^C` quits as well.
+            Some(Command::Quit) => break 'gameloop,
+            None if key == Key::CtrlC => break 'gameloop,
 
-                // If the previous input and the current input are the same, when the cell is
-                // attempted to be revealed, this is considered a double press. In that case, the
-                // neighbouring cells are to be revealed too, when possible.
-                if previous_input == input {
-                    f.apply_action(Action::RevealAround);
+            // Movement, flagging, revealing, hints, undo and redo
+            Some(command) => {
+                for action in input.actions(InputEvent::Key(command), &f, now) {
+                    if changes_probabilities(&apply(&mut f, &mut recorder, action)) {
+                        heat = None;
+                    }
                 }
             }
 
-            // Quit
-            Key::Char('q') => {
-                // q to quit
-                break 'gameloop;
+            // Mouse
+            None if sgr::is_report(&key) => {
+                if let Some(report) = sgr::read_report(&term, &key)? {
+                    let event = InputEvent::Mouse(report.event, report.cell(&viewport));
+                    for action in input.actions(event, &f, now) {
+                        if changes_probabilities(&apply(&mut f, &mut recorder, action)) {
+                            heat = None;
+                        }
+                    }
+                }
             }
 
             // Otherwise, do nothing
-            _ => {}
+            None => {}
         }
 
-        term.clear_screen()?;
+        // The end screens wait for a key, which moving the mouse would otherwise give them.
+        if f.game_state() != GameState::Running {
+            print!("{}", sgr::DISABLE);
+        }
 
         match f.game_state() {
-            GameState::Won => {
-                // Win screen
-                f.reveal_all();
-                println!("{}", style("YOU WON!!!").color256(178).bold());
-                term.write_fmt(format_args!("{}\n", f))?;
-                println!("{}", style("press any key to exit").italic());
-                let _ = term.read_char(); // get any key
-                term.clear_screen()?;
-                break 'gameloop;
-            }
-            GameState::GameOver => {
-                // Game over screen
-                f.reveal_all();
-                println!("{}", style("GAME OVER").color256(75).bold());
-                term.write_fmt(format_args!("{}\n", f))?;
-                println!("{}", style("press any key to exit").italic());
-                let _ = term.read_char(); // get any key
-                term.clear_screen()?;
-                break 'gameloop;
-            }
             GameState::Running => {
                 // The game is not over, neither has it been won. Show the number of mines left, and
                 // the total number of flags. Continue the game.
+                let probabilities = if heatmap {
+                    heat.get_or_insert_with(|| mine_probabilities(&f))
+                        .as_deref()
+                } else {
+                    None
+                };
+                if heatmap && probabilities.is_none() && message.is_none() {
+                    message = Some("no heatmap: too many possibilities to compute".to_string());
+                }
+
+                showing_message = message.is_some();
+                let status = match message.take() {
+                    Some(message) => dimmed(message),
+                    None => status_line(&f),
+                };
+                screen.draw(
+                    &term,
+                    frame(
+                        &term,
+                        &f,
+                        &look,
+                        probabilities,
+                        &mut viewport,
+                        &status,
+                        GAME_LINES,
+                    ),
+                )?;
+            }
+            state => {
+                // The game has ended. In practice mode, the losing reveal can be undone, so the
+                // field is shown as it is, such that the rest of the mines stay hidden.
+                let undoable = state == GameState::GameOver && f.is_practice();
+                let stats = Stats::of(&f);
+                let recorded = if undoable {
+                    Ok(())
+                } else {
+                    let recorded = session.finish(&f, stats);
+                    f.reveal_all();
+                    recorded
+                };
+
+                let header = match state {
+                    GameState::Won => style("YOU WON!!!").color256(178).bold(),
+                    _ => style("GAME OVER").color256(75).bold(),
+                };
+                // The lines below the field are written after it, so the screen is drawn anew.
+                screen.invalidate();
+                screen.draw(
+                    &term,
+                    frame(
+                        &term,
+                        &f,
+                        &look,
+                        None,
+                        &mut viewport,
+                        &header.to_string(),
+                        END_LINES,
+                    ),
+                )?;
+                println!();
+                println!("{}", summary(&stats));
+                if let Err(err) = recorded {
+                    println!("{}", dimmed(format!("could not record the game: {}", err)));
+                }
+                println!("{}", style(&session).color256(238));
+
+                // While recording, the replay is of a single game.
+                let restartable = recorder.is_none();
                 println!(
                     "{}",
-                    style(format!(
-                        "{} out of {} mines left",
-                        mines as isize - f.total_flags() as isize,
-                        mines
-                    ))
-                    .color256(238)
+                    style(end_options(&keymap, undoable, restartable)).italic()
                 );
-                term.write_fmt(format_args!("{}", f))?;
+
+                // Wait for the player to undo, to choose the next game, or to quit. A new game is
+                // `None` when undoing.
+                let new = loop {
+                    let key = term.read_key_raw()?;
+                    let command =
+                        KeyPress::from_console(&key).and_then(|press| keymap.command(&press));
+                    let next = match command {
+                        None if key == Key::CtrlC => break 'gameloop,
+                        Some(Command::Undo) if undoable => break None,
+                        Some(Command::Restart) if restartable => Next::Replay,
+                        Some(Command::NewGame) if restartable => Next::New,
+                        Some(Command::NewSettings) if restartable => Next::Settings,
+                        Some(Command::Quit) => break 'gameloop,
+                        _ if restartable => continue,
+                        _ => break 'gameloop,
+                    };
+                    match next_game(&term, &f, next) {
+                        Ok(Some(new)) => break Some(new),
+                        Ok(None) => {}
+                        Err(err) => println!("{}", dimmed(err)),
+                    }
+                };
+                match new {
+                    Some(new) => f = new,
+                    None => {
+                        apply(&mut f, &mut recorder, Action::Undo);
+                    }
+                }
+                heat = None;
+
+                print!("{}", sgr::ENABLE);
+                screen.invalidate();
+                let status = status_line(&f);
+                screen.draw(
+                    &term,
+                    frame(&term, &f, &look, None, &mut viewport, &status, GAME_LINES),
+                )?;
+
+                // The key that ended the game does not make a double press with the next one.
+                input.interrupt();
             }
         }
+    }
+
+    drop(restore);
+
+    if let (Some(recorder), Some(path)) = (&recorder, &opt.record) {
+        recorder.finish_to(&f, path)?;
+    }
+
+    // Save a game in progress, such that it can be resumed. A finished game removes the save.
+    f.autosave()
+}
 
-        previous_input = input;
+/// Restores the terminal when dropped, on every way out of the game including errors: the mouse
+/// reports are disabled, the cursor is shown and the alternative screen buffer is closed again.
+struct Restore<'a>(&'a Term);
+
+impl<'a> Restore<'a> {
+    /// Hides the cursor, opens the alternative screen buffer and enables the mouse reports.
+    fn setup(term: &'a Term) -> io::Result<Self> {
+        term.hide_cursor()?;
+        print!("\u{1b}[?1049h");
+        print!("{}", sgr::ENABLE);
+        Ok(Self(term))
     }
+}
 
+impl Drop for Restore<'_> {
+    fn drop(&mut self) {
+        print!("{}", sgr::DISABLE);
+        // Errors are ignored, as there is no other way left to restore the terminal.
+        let _ = self.0.show_cursor();
+        print!("\u{1b}[?1049l");
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Returns the frame to draw: the status line cut off at the width of the terminal, the part of the
+/// field that fits in the terminal with `reserved` rows left for other lines, and a line of scroll
+/// indicators if the field does not fit. The viewport is fitted to the size of the terminal first,
+/// and follows the cursor.
+pub(super) fn frame(
+    term: &Term,
+    f: &Field,
+    look: &Look,
+    probabilities: Option<&[f64]>,
+    viewport: &mut Viewport,
+    status: &str,
+    reserved: usize,
+) -> Frame {
+    let size = term.size();
+    viewport.update(size, f, reserved);
+    let styled = match probabilities {
+        Some(probabilities) => Styled::with_heatmap(f, look, probabilities),
+        None => Styled::new(f, look),
+    };
+
+    Frame {
+        size,
+        // The status line has to take a single row, where the mouse reports expect the field to
+        // start below it.
+        status: truncate_str(status, size.1 as usize, "…").to_string(),
+        cells: styled.in_viewport(*viewport).rows(),
+        footer: viewport.indicators(f).map(dimmed).into_iter().collect(),
+    }
+}
+
+/// Returns whether any of the events changes the mine probabilities, which only depend on the
+/// revealed and flagged cells.
+fn changes_probabilities(events: &[Event]) -> bool {
+    events.iter().any(|event| {
+        matches!(
+            event,
+            Event::CellRevealed(..)
+                | Event::CellHidden(..)
+                | Event::CellFlagged(..)
+                | Event::CellUnflagged(..)
+        )
+    })
+}
+
+/// Returns the time until the clock of the field shows the next tick.
+fn until_tick(f: &Field) -> Duration {
+    let tick = TICK.as_nanos();
+    Duration::from_nanos((tick - f.elapsed().as_nanos() % tick) as u64)
+}
+
+/// Returns the styled status line, showing the number of mines left, the statistics of the game
+/// and the seed of the field.
+fn status_line(f: &Field) -> String {
+    style(format!(
+        "{} out of {} mines left — {} — seed {}{}",
+        f.mines_left(),
+        f.total_mines(),
+        Stats::of(f),
+        f.seed(),
+        no_guess_note(f)
+    ))
+    .color256(238)
+    .to_string()
+}
+
+/// Returns the styled summary of the statistics, shown at the end of a game.
+fn summary(stats: &Stats) -> String {
+    let lines = [
+        format!("time        {:.1}s", stats.elapsed.as_secs_f64()),
+        format!(
+            "3BV         {}/{} ({:.2} 3BV/s)",
+            stats.solved_three_bv,
+            stats.three_bv,
+            stats.three_bv_per_second()
+        ),
+        format!(
+            "clicks      {} ({} left, {} right, {} chord)",
+            stats.clicks.total(),
+            stats.clicks.left,
+            stats.clicks.right,
+            stats.clicks.chord
+        ),
+        format!("efficiency  {:.0}%", stats.efficiency() * 100.0),
+    ];
+
+    style(lines.join("\n")).color256(245).to_string()
+}
+
+/// Returns the next game to play, or `None` if the settings prompt was left empty. Fails with a
+/// message to show if the game cannot be started.
+fn next_game(term: &Term, f: &Field, next: Next) -> Result<Option<Field>, String> {
+    let new = match next {
+        Next::Replay => same_game(f),
+        Next::New => new_game(f),
+        Next::Settings => match prompt_size(term) {
+            Ok(Some(size)) => game_with_size(f, size),
+            Ok(None) => return Ok(None),
+            Err(err) => Err(err),
+        },
+    };
+
+    new.map(Some)
+        .map_err(|err| format!("could not start a new game: {}", err))
+}
+
+/// Asks the player for the size of a new game. Returns `None` if nothing was typed, or for `^C`.
+fn prompt_size(term: &Term) -> io::Result<Option<(usize, usize, usize)>> {
+    // Typing would otherwise be mixed with the reports of the mouse.
+    print!("{}", sgr::DISABLE);
     term.show_cursor()?;
-    // Close the alternative screen buffer again.
-    print!("\u{1b}[?1049l");
+    term.write_str("\nnew game — beginner, intermediate, expert, or a size like 20x10 50: ")?;
+    let line = read_line(term);
+    term.hide_cursor()?;
+
+    match line?.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(line) => parse_size(line).map(Some),
+    }
+}
+
+/// Reads a line typed by the player, like `Term::read_line`. Returns `None` for `^C`, which would
+/// otherwise raise `SIGINT` and leave the terminal without restoring it.
+fn read_line(term: &Term) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if !term.is_term() {
+        return Ok(Some(line));
+    }
+    loop {
+        match term.read_key_raw()? {
+            Key::Char(c) => {
+                line.push(c);
+                term.write_str(c.encode_utf8(&mut [0; 4]))?;
+            }
+            Key::Backspace if line.pop().is_some() => term.clear_chars(1)?,
+            Key::Enter => {
+                term.write_line("")?;
+                return Ok(Some(line));
+            }
+            Key::CtrlC => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the keys that can be pressed at the end of a game.
+fn end_options(keymap: &Keymap, undoable: bool, restartable: bool) -> String {
+    let mut commands = Vec::new();
+    if undoable {
+        commands.push((Command::Undo, "to undo"));
+    }
+    if restartable {
+        commands.extend([
+            (Command::Restart, "to play this seed again"),
+            (Command::NewGame, "for a new game"),
+            (Command::NewSettings, "for new settings"),
+            (Command::Quit, "to quit"),
+        ]);
+    }
+
+    // Commands that are not bound to a key are left out.
+    let mut options: Vec<String> = commands
+        .into_iter()
+        .filter_map(|(command, what)| Some(format!("{} {}", keymap.key(command)?, what)))
+        .collect();
+    if !restartable {
+        options.push(
+            if undoable {
+                "any other key to exit"
+            } else {
+                "any key to exit"
+            }
+            .to_string(),
+        );
+    }
+
+    format!("press {}", options.join(", "))
+}
 
-    Ok(())
+/// Returns a message, styled to be less prominent than the field.
+fn dimmed(message: impl std::fmt::Display) -> String {
+    style(message.to_string())
+        .color256(238)
+        .italic()
+        .to_string()
 }
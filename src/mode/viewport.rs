@@ -0,0 +1,196 @@
+//! The part of the field that is shown in the terminal.
+//!
+//! Large fields do not fit in the terminal. The viewport is the rectangle of cells that is shown,
+//! which follows the cursor such that a margin of cells stays visible around it, like scrolling
+//! in a text editor.
+
+use mine::Field;
+use std::ops::Range;
+
+/// The number of cells kept visible between the cursor and the edges of the viewport, where the
+/// field allows it.
+const MARGIN: usize = 3;
+/// The number of terminal columns a cell takes: a space and its character.
+pub const CELL_WIDTH: usize = 2;
+
+/// A rectangle of cells in the field, by the position of its top left cell and its size.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Viewport {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Viewport {
+    /// Fits the viewport to a terminal of `(rows, columns)`, of which `reserved` rows are taken
+    /// by other lines, and scrolls it to keep the cursor in view.
+    ///
+    /// This is done before every draw, such that changes to the size of the terminal are picked
+    /// up.
+    pub fn update(&mut self, (rows, columns): (u16, u16), field: &Field, reserved: usize) {
+        let rows = (rows as usize).saturating_sub(reserved).max(1);
+        let columns = (columns as usize / CELL_WIDTH).max(1);
+        self.width = field.width().min(columns);
+        self.height = field.height().min(rows);
+
+        self.x = follow(self.x, self.width, field.width(), field.cursor_pos_x());
+        self.y = follow(self.y, self.height, field.height(), field.cursor_pos_y());
+    }
+
+    /// Scrolls the viewport by a number of rows, down if positive and up if negative. Returns the
+    /// position the cursor is moved to along with it, such that it stays at the same place on the
+    /// screen, as far as the field allows.
+    pub fn scroll(&mut self, rows: isize, field: &Field) -> (usize, usize) {
+        let last = field.height().saturating_sub(self.height);
+        self.y = self.y.saturating_add_signed(rows).min(last);
+
+        let y = field
+            .cursor_pos_y()
+            .saturating_add_signed(rows)
+            .min(field.height() - 1);
+        (field.cursor_pos_x(), y)
+    }
+
+    /// Returns the number of rows shown.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the columns of the field that are shown.
+    pub fn columns(&self) -> Range<usize> {
+        self.x..self.x + self.width
+    }
+
+    /// Returns the rows of the field that are shown.
+    pub fn rows(&self) -> Range<usize> {
+        self.y..self.y + self.height
+    }
+
+    /// Returns the position in the field of the cell shown at the column and row of the viewport,
+    /// if there is one.
+    pub fn cell_at(&self, column: usize, row: usize) -> Option<(usize, usize)> {
+        (column < self.width && row < self.height).then_some((self.x + column, self.y + row))
+    }
+
+    /// Returns a line that tells how many rows and columns of the field are out of view on each
+    /// side, like `▲ 12 rows  ▼ 40 rows  ▶ 3 columns`. Returns `None` if the whole field is shown.
+    pub fn indicators(&self, field: &Field) -> Option<String> {
+        let hidden = [
+            ('▲', self.y, "row"),
+            ('▼', field.height() - self.y - self.height, "row"),
+            ('◀', self.x, "column"),
+            ('▶', field.width() - self.x - self.width, "column"),
+        ];
+
+        let indicators: Vec<String> = hidden
+            .iter()
+            .filter(|(_, count, _)| *count > 0)
+            .map(|(arrow, count, unit)| {
+                format!(
+                    "{} {} {}{}",
+                    arrow,
+                    count,
+                    unit,
+                    if *count == 1 { "" } else { "s" }
+                )
+            })
+            .collect();
+        (!indicators.is_empty()).then(|| indicators.join("  "))
+    }
+}
+
+/// Returns the offset along one axis of a viewport of `size` cells on a field of `total` cells,
+/// scrolled as little as possible from `offset` to keep a margin around the cursor.
+fn follow(offset: usize, size: usize, total: usize, cursor: usize) -> usize {
+    let margin = MARGIN.min(size.saturating_sub(1) / 2);
+    let offset = if cursor < offset + margin {
+        cursor.saturating_sub(margin)
+    } else if cursor + margin >= offset + size {
+        cursor + margin + 1 - size
+    } else {
+        offset
+    };
+
+    offset.min(total - size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mine::Action;
+
+    /// Returns an empty field of 40 by 30 cells, with the cursor at the position.
+    fn field_at(x: usize, y: usize) -> Field {
+        let rows = vec![".".repeat(40); 30];
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        let mut f = Field::from_rows(&rows).unwrap();
+        f.apply_action(Action::CursorTo(x, y));
+        f
+    }
+
+    /// Returns the viewport of 10 columns and 8 rows, fitted to the field.
+    fn viewport(mut viewport: Viewport, f: &Field) -> Viewport {
+        viewport.update((10, 10 * CELL_WIDTH as u16), f, 2);
+        viewport
+    }
+
+    #[test]
+    fn small_field_is_shown_whole() {
+        let f = Field::from_rows(&["....", "....", "..*."]).unwrap();
+        let viewport = viewport(Viewport::default(), &f);
+        assert_eq!((viewport.columns(), viewport.rows()), (0..4, 0..3));
+        assert_eq!(viewport.indicators(&f), None);
+    }
+
+    #[test]
+    fn follows_the_cursor_with_a_margin() {
+        let view = viewport(Viewport::default(), &field_at(0, 0));
+        assert_eq!((view.columns(), view.rows()), (0..10, 0..8));
+
+        // Within the margin of the right and bottom edge, it scrolls just enough.
+        let view = viewport(view, &field_at(7, 5));
+        assert_eq!((view.columns(), view.rows()), (1..11, 1..9));
+        let view = viewport(view, &field_at(20, 15));
+        assert_eq!((view.columns(), view.rows()), (14..24, 11..19));
+
+        // Moving within the margins leaves it where it is.
+        let view = viewport(view, &field_at(17, 14));
+        assert_eq!((view.columns(), view.rows()), (14..24, 11..19));
+
+        // And back towards the top left.
+        let view = viewport(view, &field_at(16, 13));
+        assert_eq!((view.columns(), view.rows()), (13..23, 10..18));
+        assert_eq!(view.cell_at(0, 0), Some((13, 10)));
+        assert_eq!(view.cell_at(10, 0), None);
+    }
+
+    #[test]
+    fn stops_at_the_edges_of_the_field() {
+        let view = viewport(Viewport::default(), &field_at(39, 29));
+        assert_eq!((view.columns(), view.rows()), (30..40, 22..30));
+        assert_eq!(
+            view.indicators(&field_at(39, 29)).as_deref(),
+            Some("▲ 22 rows  ◀ 30 columns")
+        );
+
+        let view = viewport(view, &field_at(0, 29));
+        assert_eq!((view.columns(), view.rows()), (0..10, 22..30));
+        let view = viewport(view, &field_at(0, 0));
+        assert_eq!((view.columns(), view.rows()), (0..10, 0..8));
+    }
+
+    #[test]
+    fn scrolls_with_the_cursor() {
+        let f = field_at(5, 4);
+        let mut view = viewport(Viewport::default(), &f);
+
+        assert_eq!(view.scroll(4, &f), (5, 8));
+        assert_eq!(view.rows(), 4..12);
+        // It stops at the bottom of the field, and so does the cursor.
+        assert_eq!(view.scroll(40, &f), (5, 29));
+        assert_eq!(view.rows(), 22..30);
+        assert_eq!(view.scroll(-40, &f), (5, 0));
+        assert_eq!(view.rows(), 0..8);
+    }
+}
@@ -0,0 +1,53 @@
+//! The directories and files in which mine keeps its data and reads its configuration.
+
+use std::env;
+use std::path::PathBuf;
+
+/// Returns the directory in which mine keeps its data, such as the autosave.
+///
+/// This is `$XDG_DATA_HOME/mine`, falling back to `~/.local/share/mine`, or `%APPDATA%\mine` on
+/// Windows.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home().map(|home| home.join(".local").join("share")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(base.join("mine"))
+}
+
+/// Returns the directory in which mine looks for its configuration.
+///
+/// This is `$XDG_CONFIG_HOME/mine`, falling back to `~/.config/mine`, or `%APPDATA%\mine` on
+/// Windows.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home().map(|home| home.join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(base.join("mine"))
+}
+
+/// Returns the path of the configuration file, which holds the keymap.
+pub fn config() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Returns the path of the game that is saved when quitting, and continued with `--resume`.
+pub fn autosave() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("autosave.mine"))
+}
+
+/// Returns the path of the records of finished games.
+pub fn records() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("records.mine"))
+}
+
+fn home() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
@@ -0,0 +1,236 @@
+//! Exact mine probabilities for the hidden cells of a field.
+//!
+//! Like the solver, this only reads what is visible on the field. The hidden cells next to revealed
+//! numbers (the frontier) are split into independent components, and every assignment of mines to
+//! a component is enumerated by the solver. The cells that touch no number share the mines that
+//! are left over, so an assignment that leaves `m` mines for `r` such cells is weighted by the
+//! number of ways to place them, `C(r, m)`. Combining the components with these weights gives the
+//! exact probability that each hidden cell is a mine, under the assumption that every layout that
+//! agrees with the visible field is equally likely.
+
+use crate::field::Field;
+use crate::solver::{components, Board, Knowledge, ENUMERATION_BUDGET};
+
+/// Returns the probability that each cell of the field is a mine, row by row.
+///
+/// Revealed cells have a probability of 0, and flagged cells are trusted to be mines. Returns
+/// `None` if the frontier is too large to enumerate, or if the visible field is contradictory.
+pub fn mine_probabilities(field: &Field) -> Option<Vec<f64>> {
+    let mut board = Board::new(field);
+    // Certain deductions do not change the probabilities, but make the components smaller.
+    board.deduce();
+
+    let mut probabilities: Vec<f64> = board
+        .knowledge
+        .iter()
+        .map(|knowledge| match knowledge {
+            Knowledge::Mine | Knowledge::Flagged => 1.0,
+            Knowledge::Safe | Knowledge::Revealed | Knowledge::Unknown => 0.0,
+        })
+        .collect();
+
+    let mines_left = board.mines_left();
+    let components = components(board.constraints());
+    let tallies = components
+        .iter()
+        .map(|component| component.enumerate(mines_left, ENUMERATION_BUDGET, None))
+        .collect::<Option<Vec<_>>>()?;
+
+    // The unknown cells that are not part of any component.
+    let mut in_component = vec![false; probabilities.len()];
+    for component in &components {
+        for &cell in &component.cells {
+            in_component[cell] = true;
+        }
+    }
+    let others: Vec<usize> = board
+        .unknown()
+        .into_iter()
+        .filter(|&cell| !in_component[cell])
+        .collect();
+
+    let weight = Weights::new(others.len(), mines_left);
+
+    // The number of assignments of every component, by number of mines.
+    let distributions: Vec<Vec<f64>> = tallies
+        .iter()
+        .map(|tally| tally.solutions.iter().map(|&n| n as f64).collect())
+        .collect();
+
+    // The probabilities of the cells in each component.
+    for (c, (component, tally)) in components.iter().zip(&tallies).enumerate() {
+        // The distribution of the number of mines in all other components.
+        let rest = convolve_all(
+            distributions
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != c)
+                .map(|(_, distribution)| distribution),
+        );
+
+        let mut total = 0.0;
+        let mut mines = vec![0.0; component.cells.len()];
+        for (k, &solutions) in tally.solutions.iter().enumerate() {
+            if solutions == 0 {
+                continue;
+            }
+            let weight: f64 = rest
+                .iter()
+                .enumerate()
+                .map(|(r, &count)| count * weight.get(k + r))
+                .sum();
+
+            total += solutions as f64 * weight;
+            for (i, &count) in tally.mines[k].iter().enumerate() {
+                mines[i] += count as f64 * weight;
+            }
+        }
+
+        if total <= 0.0 {
+            return None;
+        }
+        for (&cell, mines) in component.cells.iter().zip(mines) {
+            probabilities[cell] = mines / total;
+        }
+    }
+
+    // The probability of the other cells, which all share the mines that are left.
+    if !others.is_empty() {
+        let all = convolve_all(distributions.iter());
+        let mut total = 0.0;
+        let mut mines = 0.0;
+        for (k, &count) in all.iter().enumerate() {
+            let weight = count * weight.get(k);
+            total += weight;
+            mines += weight * mines_left.saturating_sub(k) as f64 / others.len() as f64;
+        }
+
+        if total <= 0.0 {
+            return None;
+        }
+        for &cell in &others {
+            probabilities[cell] = mines / total;
+        }
+    }
+
+    Some(probabilities)
+}
+
+/// Convolves the distributions of the number of mines of independent components into the
+/// distribution of their total number of mines.
+///
+/// The result is rescaled after every step to keep it within the range of `f64`. Only the ratios
+/// between its values are meaningful.
+fn convolve_all<'d>(distributions: impl Iterator<Item = &'d Vec<f64>>) -> Vec<f64> {
+    let mut result = vec![1.0];
+    for distribution in distributions {
+        let mut next = vec![0.0; result.len() + distribution.len() - 1];
+        for (a, &x) in result.iter().enumerate() {
+            for (b, &y) in distribution.iter().enumerate() {
+                next[a + b] += x * y;
+            }
+        }
+
+        let max = next.iter().copied().fold(0.0, f64::max);
+        if max > 0.0 {
+            next.iter_mut().for_each(|value| *value /= max);
+        }
+        result = next;
+    }
+
+    result
+}
+
+/// The relative number of ways to place the mines left over by the frontier on the other cells.
+struct Weights {
+    /// The weight for every number of mines on the frontier, indexed by that number.
+    weights: Vec<f64>,
+}
+
+impl Weights {
+    /// Computes `C(others, mines_left - k)` for every `k`, relative to the largest of them.
+    ///
+    /// The binomial coefficients quickly outgrow `f64`, so they are computed as logarithms.
+    fn new(others: usize, mines_left: usize) -> Self {
+        // ln C(others, j) for j in 0..=mines_left, using C(n, j + 1) = C(n, j) (n - j) / (j + 1).
+        let mut ln_binomials = Vec::with_capacity(mines_left + 1);
+        let mut ln_binomial = 0.0;
+        for j in 0..=mines_left {
+            if j > others {
+                ln_binomials.push(f64::NEG_INFINITY);
+                continue;
+            }
+            ln_binomials.push(ln_binomial);
+            ln_binomial += ((others - j) as f64).ln() - ((j + 1) as f64).ln();
+        }
+
+        let max = ln_binomials
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if max == f64::NEG_INFINITY {
+            // There are more mines left than cells to place them on.
+            return Self {
+                weights: Vec::new(),
+            };
+        }
+        let weights = (0..=mines_left)
+            .map(|k| (ln_binomials[mines_left - k] - max).exp())
+            .collect();
+
+        Self { weights }
+    }
+
+    /// Returns the weight of `k` mines on the frontier, which is 0 if there are not enough mines
+    /// left or too many for the other cells.
+    fn get(&self, k: usize) -> f64 {
+        self.weights.get(k).copied().unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::fixtures::field;
+    use crate::field::SafeStart;
+
+    fn assert_close(found: &[f64], expected: &[f64]) {
+        assert_eq!(found.len(), expected.len());
+        for (found, expected) in found.iter().zip(expected) {
+            assert!(
+                (found - expected).abs() < 1e-9,
+                "{:?} != {:?}",
+                found,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn untouched_field_is_uniform() {
+        let f = Field::with_seed(3, 3, 2, SafeStart::Off, 0).unwrap();
+        assert_close(&mine_probabilities(&f).unwrap(), &[2.0 / 9.0; 9]);
+    }
+
+    #[test]
+    fn number_shares_its_mine() {
+        // One mine next to the 1, and one among the five cells away from it.
+        let f = field(&[".*.", "...", "..*"], &[(0, 0)]);
+        let (near, far) = (1.0 / 3.0, 1.0 / 5.0);
+        assert_close(
+            &mine_probabilities(&f).unwrap(),
+            &[0.0, near, far, near, near, far, far, far, far],
+        );
+    }
+
+    #[test]
+    fn assignments_are_weighted_by_the_cells_away_from_numbers() {
+        // The two 1s hold either the mine between them, leaving one mine for the three cells on
+        // the right in 3 ways, or the two mines around them, leaving none in 1 way.
+        let f = field(&["..*...*."], &[(1, 0), (3, 0)]);
+        assert_close(
+            &mine_probabilities(&f).unwrap(),
+            &[0.25, 0.0, 0.75, 0.0, 0.25, 0.25, 0.25, 0.25],
+        );
+    }
+}
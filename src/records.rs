@@ -0,0 +1,505 @@
+//! Records of finished games, kept across sessions.
+//!
+//! Every game that is won or lost is appended to a records file in the data directory. The
+//! statistics per board, like the number of wins, the win streaks and the best times, and the
+//! leaderboards are derived from these games when the records are read. The file is line based,
+//! with one game per line after the header:
+//!
+//! ```text
+//! mine records 1
+//! 1760696523 9 9 10 won 10523 31 31 25 4 1
+//! 1760696612 30 16 99 lost 48210 178 92 80 31 6
+//! ```
+//!
+//! A game line holds the moment it ended in seconds since the Unix epoch, the width, height and
+//! number of mines of the field, whether it was won or lost, its time in milliseconds, its 3BV and
+//! the cleared part of it, and its left, right and chord clicks. Games in practice mode are not
+//! recorded, since their losing reveals can be undone.
+
+use crate::difficulty::Difficulty;
+use crate::field::{Field, GameState};
+use crate::format::{self, invalid, parse};
+use crate::paths;
+use crate::stats::{Clicks, Stats};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &str = "mine records";
+const VERSION: u32 = 1;
+
+/// The configuration of a field that games are compared on: its size and number of mines.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Board {
+    /// The number of columns.
+    pub width: usize,
+    /// The number of rows.
+    pub height: usize,
+    /// The number of mines.
+    pub mines: usize,
+}
+
+impl Board {
+    /// Returns the board of the field.
+    pub fn of(field: &Field) -> Self {
+        Self {
+            width: field.width(),
+            height: field.height(),
+            mines: field.total_mines(),
+        }
+    }
+
+    /// Returns the difficulty preset of the board, or `Difficulty::Custom` if it has none.
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::of(self.width, self.height, self.mines)
+    }
+}
+
+/// Writes the board like `expert (30x16, 99 mines)`.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}x{}, {} mines)",
+            self.difficulty(),
+            self.width,
+            self.height,
+            self.mines
+        )
+    }
+}
+
+/// A finished game.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Game {
+    /// The moment the game ended, in seconds since the Unix epoch.
+    pub date: u64,
+    /// The board the game was played on.
+    pub board: Board,
+    /// Whether the game was won.
+    pub won: bool,
+    /// The statistics of the game at the moment it ended.
+    pub stats: Stats,
+}
+
+impl Game {
+    /// Returns the game played on the field, ending now, or `None` if the field should not be
+    /// recorded: when the game is still running or is played in practice mode.
+    ///
+    /// The statistics must be taken before the field is revealed at the end of the game, since
+    /// revealing it clears all of its 3BV.
+    pub fn of(field: &Field, stats: Stats) -> Option<Self> {
+        let won = match field.game_state() {
+            GameState::Running => return None,
+            _ if field.is_practice() => return None,
+            GameState::Won => true,
+            GameState::GameOver => false,
+        };
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+
+        Some(Self {
+            date,
+            board: Board::of(field),
+            won,
+            stats,
+        })
+    }
+
+    /// Returns the date the game ended on as `YYYY-MM-DD`, in UTC.
+    pub fn day(&self) -> String {
+        let (year, month, day) = civil_from_days((self.date / 86400) as i64);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    fn write(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(
+            w,
+            "{} {} {} {} {} {} {} {} {} {} {}",
+            self.date,
+            self.board.width,
+            self.board.height,
+            self.board.mines,
+            if self.won { "won" } else { "lost" },
+            self.stats.elapsed.as_millis(),
+            self.stats.three_bv,
+            self.stats.solved_three_bv,
+            self.stats.clicks.left,
+            self.stats.clicks.right,
+            self.stats.clicks.chord
+        )
+    }
+}
+
+impl FromStr for Game {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<&str> = s.split(' ').collect();
+        match values[..] {
+            [date, width, height, mines, result, elapsed_ms, three_bv, solved, left, right, chord] => {
+                Ok(Self {
+                    date: parse(date)?,
+                    board: Board {
+                        width: parse(width)?,
+                        height: parse(height)?,
+                        mines: parse(mines)?,
+                    },
+                    won: match result {
+                        "won" => true,
+                        "lost" => false,
+                        _ => return Err(invalid(format!("invalid result '{}'", result))),
+                    },
+                    stats: Stats {
+                        elapsed: Duration::from_millis(parse(elapsed_ms)?),
+                        clicks: Clicks {
+                            left: parse(left)?,
+                            right: parse(right)?,
+                            chord: parse(chord)?,
+                        },
+                        three_bv: parse(three_bv)?,
+                        solved_three_bv: parse(solved)?,
+                    },
+                })
+            }
+            _ => Err(invalid(format!("invalid game '{}'", s))),
+        }
+    }
+}
+
+/// The statistics of all games played on a single board.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BoardRecord {
+    /// The board the games were played on.
+    pub board: Board,
+    /// The number of games played.
+    pub played: usize,
+    /// The number of games won.
+    pub won: usize,
+    /// The number of games won in a row, up to the last game.
+    pub streak: usize,
+    /// The largest number of games won in a row.
+    pub best_streak: usize,
+    /// The time of the fastest win, if any game was won.
+    pub best_time: Option<Duration>,
+}
+
+/// All recorded games, in the order they were played.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Records {
+    /// The games, oldest first.
+    pub games: Vec<Game>,
+}
+
+impl Records {
+    /// Reads records from the reader.
+    pub fn read(r: impl Read) -> io::Result<Self> {
+        let mut lines = BufReader::new(r).lines();
+
+        let header = lines.next().unwrap_or_else(|| Ok(String::new()))?;
+        format::read_header(&header, MAGIC, VERSION)?;
+
+        let mut games = Vec::new();
+        for line in lines {
+            let line = line?;
+            if !line.is_empty() {
+                games.push(line.parse()?);
+            }
+        }
+
+        Ok(Self { games })
+    }
+
+    /// Loads the records from the data directory. If nothing has been recorded yet, the records
+    /// are empty.
+    pub fn load() -> io::Result<Self> {
+        match File::open(records_path()?) {
+            Ok(file) => Self::read(file),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Appends the game to the records in the data directory.
+    pub fn append(game: &Game) -> io::Result<()> {
+        let path = records_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if file.metadata()?.len() == 0 {
+            format::write_header(&mut file, MAGIC, VERSION)?;
+        }
+        game.write(&mut file)?;
+        file.flush()
+    }
+
+    /// Returns the statistics of every board that games were played on. The difficulty presets
+    /// come first, followed by the custom boards from small to large.
+    pub fn boards(&self) -> Vec<BoardRecord> {
+        let mut records: Vec<BoardRecord> = Vec::new();
+        for game in &self.games {
+            let index = match records.iter().position(|r| r.board == game.board) {
+                Some(index) => index,
+                None => {
+                    records.push(BoardRecord {
+                        board: game.board,
+                        played: 0,
+                        won: 0,
+                        streak: 0,
+                        best_streak: 0,
+                        best_time: None,
+                    });
+                    records.len() - 1
+                }
+            };
+
+            let record = &mut records[index];
+            record.played += 1;
+            if game.won {
+                record.won += 1;
+                record.streak += 1;
+                record.best_streak = record.best_streak.max(record.streak);
+                record.best_time = Some(match record.best_time {
+                    Some(best) => best.min(game.stats.elapsed),
+                    None => game.stats.elapsed,
+                });
+            } else {
+                record.streak = 0;
+            }
+        }
+
+        records.sort_by_key(|r| {
+            let board = r.board;
+            (
+                board.difficulty() == Difficulty::Custom,
+                board.width * board.height,
+                board.mines,
+                board.width,
+            )
+        });
+        records
+    }
+
+    /// Returns the fastest `count` wins on the board, fastest first. Of equally fast wins, the
+    /// earliest comes first.
+    pub fn leaderboard(&self, board: Board, count: usize) -> Vec<Game> {
+        let mut wins: Vec<Game> = self
+            .games
+            .iter()
+            .filter(|game| game.won && game.board == board)
+            .copied()
+            .collect();
+        wins.sort_by_key(|game| (game.stats.elapsed, game.date));
+        wins.truncate(count);
+        wins
+    }
+}
+
+/// Returns the path of the records file in the data directory.
+pub fn records_path() -> io::Result<PathBuf> {
+    paths::records().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "could not determine the data directory for the records",
+        )
+    })
+}
+
+/// Returns the year, month and day of the date `days` days after 1970-01-01, in the proleptic
+/// Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's algorithm, which counts in eras of 400 years starting on March 1st.
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEGINNER: Board = Board {
+        width: 9,
+        height: 9,
+        mines: 10,
+    };
+
+    /// Returns a game on the board, with the given date, result and time in milliseconds.
+    fn game(date: u64, board: Board, won: bool, elapsed_ms: u64) -> Game {
+        let result = if won { "won" } else { "lost" };
+        format!(
+            "{} {} {} {} {} {} 20 20 25 4 1",
+            date, board.width, board.height, board.mines, result, elapsed_ms
+        )
+        .parse()
+        .unwrap()
+    }
+
+    fn recorded(games: &[Game]) -> Records {
+        Records {
+            games: games.to_vec(),
+        }
+    }
+
+    #[test]
+    fn records_file_is_read_and_written() {
+        let file = "mine records 1\n\
+                    1760696523 9 9 10 won 10523 31 31 25 4 1\n\
+                    \n\
+                    1760696612 30 16 99 lost 48210 178 92 80 31 6\n";
+        let records = Records::read(file.as_bytes()).unwrap();
+        assert_eq!(records.games.len(), 2);
+
+        let won = records.games[0];
+        assert_eq!(won.date, 1760696523);
+        assert_eq!(won.board, BEGINNER);
+        assert!(won.won);
+        assert_eq!(won.stats.elapsed, Duration::from_millis(10523));
+        assert_eq!((won.stats.three_bv, won.stats.solved_three_bv), (31, 31));
+        assert_eq!(won.stats.clicks.total(), 30);
+        assert_eq!(won.day(), "2025-10-17");
+
+        let lost = records.games[1];
+        assert_eq!(lost.board.difficulty(), Difficulty::Expert);
+        assert!(!lost.won);
+
+        let mut written = Vec::new();
+        for game in &records.games {
+            game.write(&mut written).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "1760696523 9 9 10 won 10523 31 31 25 4 1\n\
+             1760696612 30 16 99 lost 48210 178 92 80 31 6\n"
+        );
+    }
+
+    #[test]
+    fn invalid_records_are_rejected() {
+        for file in [
+            "",
+            "mine save 1\n",
+            "mine records 2\n",
+            "mine records 1\n1760696523 9 9 10 tied 10523 31 31 25 4 1\n",
+            "mine records 1\n1760696523 9 9 10 won 10523 31 31 25 4\n",
+            "mine records 1\n1760696523 9 9 -10 won 10523 31 31 25 4 1\n",
+        ] {
+            let err = Records::read(file.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", file);
+        }
+    }
+
+    #[test]
+    fn boards_are_counted_apart() {
+        let expert = Board {
+            width: 30,
+            height: 16,
+            mines: 99,
+        };
+        let custom = |mines| Board {
+            width: 9,
+            height: 9,
+            mines,
+        };
+        let records = recorded(&[
+            game(1, custom(20), true, 5000),
+            game(2, expert, false, 1000),
+            game(3, BEGINNER, true, 9000),
+            game(4, custom(5), false, 1000),
+            game(5, BEGINNER, false, 2000),
+        ]);
+
+        let boards = records.boards();
+        let order: Vec<Board> = boards.iter().map(|record| record.board).collect();
+        assert_eq!(order, [BEGINNER, expert, custom(5), custom(20)]);
+        let played: Vec<(usize, usize)> = boards
+            .iter()
+            .map(|record| (record.played, record.won))
+            .collect();
+        assert_eq!(played, [(2, 1), (1, 0), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn streaks_and_best_times() {
+        let records = recorded(&[
+            game(1, BEGINNER, true, 9000),
+            game(2, BEGINNER, true, 7000),
+            game(3, BEGINNER, true, 8000),
+            game(4, BEGINNER, false, 1000),
+            game(5, BEGINNER, true, 7000),
+        ]);
+
+        let record = records.boards()[0];
+        assert_eq!((record.played, record.won), (5, 4));
+        assert_eq!((record.streak, record.best_streak), (1, 3));
+        assert_eq!(record.best_time, Some(Duration::from_millis(7000)));
+
+        let dates: Vec<u64> = records
+            .leaderboard(BEGINNER, 3)
+            .iter()
+            .map(|game| game.date)
+            .collect();
+        assert_eq!(dates, [2, 5, 3]);
+
+        let record = recorded(&[game(1, BEGINNER, false, 1000)]).boards()[0];
+        assert_eq!((record.streak, record.best_streak), (0, 0));
+        assert_eq!(record.best_time, None);
+    }
+
+    #[test]
+    fn days_since_the_epoch() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(10956), (1999, 12, 31));
+        assert_eq!(civil_from_days(10957), (2000, 1, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11017), (2000, 3, 1));
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+        // 2100 is not a leap year.
+        assert_eq!(civil_from_days(47540), (2100, 2, 28));
+        assert_eq!(civil_from_days(47541), (2100, 3, 1));
+    }
+
+    #[test]
+    fn days_follow_each_other() {
+        let leap = |year: i64| year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days_in_month = |year, month| match month {
+            2 if leap(year) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+
+        // From 1600-01-01 to well past 2400, across several eras of 400 years.
+        let mut date = (1600, 1, 1);
+        for days in -135140..200000 {
+            assert_eq!(civil_from_days(days), date, "day {}", days);
+            let (year, month, day) = date;
+            date = if day < days_in_month(year, month) {
+                (year, month, day + 1)
+            } else if month < 12 {
+                (year, month + 1, 1)
+            } else {
+                (year + 1, 1, 1)
+            };
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! Recording and reading of replays.
+//!
+//! A replay holds everything needed to play a game back: the settings of the field, the layout of
+//! its mines, every action with the time at which it was applied, and the state the game ended
+//! in. It is written in a line based text format, similar to the save format:
+//!
+//! ```text
+//! mine replay 2
+//! width 9
+//! height 9
+//! mines 10
+//! seed 4242
+//! safe_start opening
+//! practice false
+//! layout
+//! 000000010
+//! ...
+//! actions
+//! 1520 cursor-down
+//! 1804 reveal
+//! ...
+//! result won
+//! ```
+//!
+//! The layout rows hold a `1` for every mine. If nothing was revealed during the game, there is no
+//! layout and the field is generated from its seed instead. Every action line starts with the
+//! number of milliseconds since the recording started.
+//!
+//! When the format changes, `VERSION` is incremented and reading older versions is kept working.
+//! Version 2 adds the `cursor-to` action, so version 1 replays can be read as they are.
+
+use crate::field::{Action, Event, Field, FieldError, GameState, SafeStart};
+use crate::format::{self, invalid, parse};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const MAGIC: &str = "mine replay";
+const VERSION: u32 = 2;
+
+/// A single action in a replay.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Step {
+    /// The time since the start of the recording.
+    pub at: Duration,
+    /// The action that was applied.
+    pub action: Action,
+}
+
+/// A recorded game.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Replay {
+    /// The number of columns of the field.
+    pub width: usize,
+    /// The number of rows of the field.
+    pub height: usize,
+    /// The number of mines in the field.
+    pub mines: usize,
+    /// The seed the field was generated from.
+    pub seed: u64,
+    /// Which cells were kept free of mines on the first reveal.
+    pub safe_start: SafeStart,
+    /// Whether the game was played in practice mode.
+    pub practice: bool,
+    /// The mines row by row, or `None` if they were never placed.
+    pub layout: Option<Vec<bool>>,
+    /// The actions in the order they were applied.
+    pub steps: Vec<Step>,
+    /// The state the game ended in.
+    pub result: GameState,
+}
+
+impl Replay {
+    /// Returns the field as it was at the start of the recording.
+    ///
+    /// Panics if the settings of the replay do not make a valid field. This is checked when a
+    /// replay is read.
+    pub fn field(&self) -> Field {
+        let mut f = self
+            .try_field()
+            .expect("the settings of the replay make a valid field");
+        f.set_practice(self.practice);
+
+        f
+    }
+
+    fn try_field(&self) -> Result<Field, FieldError> {
+        match &self.layout {
+            Some(layout) => {
+                Field::from_layout(self.height, self.width, layout, self.safe_start, self.seed)
+            }
+            None => Field::with_seed(
+                self.height,
+                self.width,
+                self.mines,
+                self.safe_start,
+                self.seed,
+            ),
+        }
+    }
+
+    /// Returns the field after the first `steps` actions have been applied.
+    pub fn field_at(&self, steps: usize) -> Field {
+        let mut f = self.field();
+        for step in self.steps.iter().take(steps) {
+            f.apply_action(step.action);
+        }
+
+        f
+    }
+
+    /// Plays all actions back and checks whether the game ends in the recorded state.
+    ///
+    /// Returns the state the game actually ended in as an error if it does not.
+    pub fn verify(&self) -> Result<(), GameState> {
+        let state = self.field_at(self.steps.len()).game_state();
+        if state == self.result {
+            Ok(())
+        } else {
+            Err(state)
+        }
+    }
+
+    /// Writes the replay to the writer.
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        format::write_header(&mut w, MAGIC, VERSION)?;
+        writeln!(w, "width {}", self.width)?;
+        writeln!(w, "height {}", self.height)?;
+        writeln!(w, "mines {}", self.mines)?;
+        writeln!(w, "seed {}", self.seed)?;
+        writeln!(w, "safe_start {}", self.safe_start)?;
+        writeln!(w, "practice {}", self.practice)?;
+
+        if let Some(layout) = &self.layout {
+            writeln!(w, "layout")?;
+            for row in layout.chunks(self.width) {
+                let line: String = row.iter().map(|&m| if m { '1' } else { '0' }).collect();
+                writeln!(w, "{}", line)?;
+            }
+        }
+
+        writeln!(w, "actions")?;
+        for step in &self.steps {
+            writeln!(w, "{} {}", step.at.as_millis(), step.action)?;
+        }
+        writeln!(w, "result {}", self.result)
+    }
+
+    /// Reads a replay from the reader.
+    pub fn read(r: impl Read) -> io::Result<Self> {
+        let mut lines = BufReader::new(r).lines();
+        let mut next_line = || -> io::Result<String> {
+            lines
+                .next()
+                .unwrap_or_else(|| Err(invalid("unexpected end of replay file".to_string())))
+        };
+
+        let header = next_line()?;
+        format::read_header(&header, MAGIC, VERSION)?;
+
+        let mut value = |key: &str| -> io::Result<String> {
+            let line = next_line()?;
+            match line.split_once(' ') {
+                Some((k, v)) if k == key => Ok(v.to_string()),
+                _ => Err(invalid(format!("expected '{}', found '{}'", key, line))),
+            }
+        };
+
+        let width: usize = parse(&value("width")?)?;
+        let height: usize = parse(&value("height")?)?;
+        let mines = parse(&value("mines")?)?;
+        let seed = parse(&value("seed")?)?;
+        let safe_start = parse(&value("safe_start")?)?;
+        let practice = parse(&value("practice")?)?;
+
+        format::field_cells(width, height)?;
+
+        let mut line = next_line()?;
+        let mut layout = None;
+        if line == "layout" {
+            // The size is not trusted to allocate for, the cells are only kept once they are read.
+            let mut mines = Vec::new();
+            for _ in 0..height {
+                let row = next_line()?;
+                if row.len() != width {
+                    return Err(invalid(format!("expected a row of {} cells", width)));
+                }
+                for c in row.chars() {
+                    match c {
+                        '0' => mines.push(false),
+                        '1' => mines.push(true),
+                        _ => return Err(invalid(format!("invalid cell '{}'", c))),
+                    }
+                }
+            }
+            layout = Some(mines);
+            line = next_line()?;
+        }
+
+        if line != "actions" {
+            return Err(invalid(format!("expected 'actions', found '{}'", line)));
+        }
+        let mut steps = Vec::new();
+        let result = loop {
+            let line = next_line()?;
+            let (first, second) = line
+                .split_once(' ')
+                .ok_or_else(|| invalid(format!("invalid line '{}'", line)))?;
+            if first == "result" {
+                break parse(second)?;
+            }
+
+            steps.push(Step {
+                at: Duration::from_millis(parse(first)?),
+                action: parse(second)?,
+            });
+        };
+
+        let replay = Self {
+            width,
+            height,
+            mines,
+            seed,
+            safe_start,
+            practice,
+            layout,
+            steps,
+            result,
+        };
+        replay.try_field().map_err(|err| invalid(err.to_string()))?;
+
+        Ok(replay)
+    }
+
+    /// Reads a replay from the file at `path`.
+    pub fn read_from(path: &Path) -> io::Result<Self> {
+        Self::read(File::open(path)?)
+    }
+}
+
+/// Records the actions applied to a field, such that the game can be written as a replay.
+pub struct Recorder {
+    started: Instant,
+    initial: Field,
+    steps: Vec<Step>,
+}
+
+impl Recorder {
+    /// Starts recording the game on the field, which should not have been played yet.
+    pub fn new(field: &Field) -> Self {
+        Self {
+            started: Instant::now(),
+            initial: field.clone(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Records the action, timed from the start of the recording.
+    pub fn record(&mut self, action: Action) {
+        self.steps.push(Step {
+            at: self.started.elapsed(),
+            action,
+        })
+    }
+
+    /// Finishes the recording of the game, which has been played up to the state of `field`.
+    ///
+    /// The mines are only placed on the first reveal, so the layout is taken from the field at the
+    /// end of the game.
+    pub fn finish(&self, field: &Field) -> Replay {
+        Replay {
+            width: self.initial.width(),
+            height: self.initial.height(),
+            mines: self.initial.total_mines(),
+            seed: self.initial.seed(),
+            safe_start: self.initial.safe_start(),
+            practice: self.initial.is_practice(),
+            layout: field.mine_layout(),
+            steps: self.steps.clone(),
+            result: field.game_state(),
+        }
+    }
+
+    /// Finishes the recording and writes the replay to the file at `path`.
+    pub fn finish_to(&self, field: &Field, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = File::create(path)?;
+        self.finish(field).write(&mut file)?;
+        file.flush()
+    }
+}
+
+/// Applies the action to the field, and records it if the game is being recorded. Returns the
+/// events of the action, see `Field::apply_action`.
+pub fn apply(field: &mut Field, recorder: &mut Option<Recorder>, action: Action) -> Vec<Event> {
+    let events = field.apply_action(action);
+    if let Some(recorder) = recorder {
+        recorder.record(action)
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the replay of a short game on a seeded field, which is still running.
+    fn recorded() -> Replay {
+        let mut f = Field::with_seed(9, 9, 10, SafeStart::Opening, 4242).unwrap();
+        let mut recorder = Some(Recorder::new(&f));
+        for action in [
+            Action::Reveal,
+            Action::CursorTo(0, 4),
+            Action::Flag,
+            Action::CursorDown,
+        ] {
+            apply(&mut f, &mut recorder, action);
+        }
+        recorder.unwrap().finish(&f)
+    }
+
+    #[test]
+    fn round_trip_keeps_the_replay() {
+        let mut replay = recorded();
+        // The times are written in whole milliseconds.
+        for step in &mut replay.steps {
+            step.at = Duration::from_millis(step.at.as_millis() as u64);
+        }
+        let mut out = Vec::new();
+        replay.write(&mut out).unwrap();
+
+        assert_eq!(Replay::read(&out[..]).unwrap(), replay);
+    }
+
+    #[test]
+    fn verify_plays_the_game_back() {
+        let mut replay = recorded();
+        assert_eq!(replay.verify(), Ok(()));
+        assert_eq!(replay.field_at(1).mine_layout(), replay.layout);
+
+        replay.result = GameState::Won;
+        assert_eq!(replay.verify(), Err(GameState::Running));
+    }
+
+    #[test]
+    fn version_1_is_read_without_a_layout() {
+        let replay = "mine replay 1\nwidth 3\nheight 2\nmines 1\nseed 7\nsafe_start cell\n\
+                      practice false\nactions\n0 cursor-right\n250 reveal\nresult running\n";
+
+        let replay = Replay::read(replay.as_bytes()).unwrap();
+        assert_eq!(replay.layout, None);
+        assert_eq!(replay.steps.len(), 2);
+        assert_eq!(replay.steps[1].at, Duration::from_millis(250));
+        assert_eq!(replay.steps[1].action, Action::Reveal);
+    }
+
+    #[test]
+    fn invalid_replays_are_rejected() {
+        let huge = "mine replay 2\nwidth 4294967296\nheight 4294967296\nmines 1\nseed 7\n\
+                    safe_start cell\npractice false\nlayout\n";
+        let short_row = "mine replay 2\nwidth 3\nheight 1\nmines 1\nseed 7\nsafe_start cell\n\
+                         practice false\nlayout\n01\n";
+        for replay in ["mine replay 3\n", "mine save 2\n", huge, short_row] {
+            let err = Replay::read(replay.as_bytes()).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
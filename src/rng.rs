@@ -0,0 +1,85 @@
+//! A small, seedable pseudo random number generator for generating fields.
+//!
+//! A seed must produce the same mine layout on every version and platform, so that a game can be
+//! shared and reproduced. The generators in `rand` do not promise this: the algorithm behind
+//! `StdRng` and the way `shuffle` draws its numbers may change between releases. Therefore the
+//! field is generated with this generator instead.
+//!
+//! The generator implements SplitMix64 (Steele, Lea & Flood, 2014) and works on `u64` values
+//! only, so its output does not depend on the pointer width of the platform. Do not change the
+//! algorithm, or previously shared seeds will produce different fields.
+
+/// SplitMix64 pseudo random number generator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns the next pseudo random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed value in `0..n`.
+    ///
+    /// Values from the top of the `u64` range that would make the result biased towards small
+    /// values are rejected and drawn again.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "cannot draw a value below 0");
+        let zone = u64::MAX - (u64::MAX % n);
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Shuffles the slice in place with the Fisher-Yates algorithm, walking from the last element
+    /// to the first.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            slice.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_outputs_are_those_of_splitmix64() {
+        let mut rng = Rng::new(0);
+        let outputs: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(
+            outputs,
+            [
+                0xe220_a839_7b1d_cdaf,
+                0x6e78_9e6a_a1b9_65f4,
+                0x06c4_5d18_8009_454f
+            ]
+        );
+    }
+
+    #[test]
+    fn same_seed_draws_the_same_values() {
+        let mut a = Rng::new(4242);
+        let mut b = Rng::new(4242);
+        for n in 1..100 {
+            let value = a.below(n);
+            assert!(value < n);
+            assert_eq!(value, b.below(n));
+        }
+    }
+}
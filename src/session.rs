@@ -0,0 +1,64 @@
+use mine::records::{Game, Records};
+use mine::{Field, Stats};
+use std::fmt;
+use std::io;
+
+/// The games finished since the program was started, kept while new games are played.
+#[derive(Default)]
+pub struct Session {
+    games: Vec<Game>,
+}
+
+impl Session {
+    /// Ends the game played on the field, which is added to the session and to the records. Games
+    /// that are not recorded, like those in practice mode, are left out of the session as well.
+    ///
+    /// The statistics must be taken before the field is revealed at the end of the game.
+    pub fn finish(&mut self, f: &Field, stats: Stats) -> io::Result<()> {
+        match Game::of(f, stats) {
+            Some(game) => {
+                self.games.push(game);
+                Records::append(&game)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writes the session on a single line, like
+/// `this session: 2 of 3 won — streak 1 — best 12.3s on beginner`. The best time is that of the
+/// board of the last game.
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let won = self.games.iter().filter(|game| game.won).count();
+        let streak = self.games.iter().rev().take_while(|game| game.won).count();
+        write!(
+            f,
+            "this session: {} of {} won — streak {}",
+            won,
+            self.games.len(),
+            streak
+        )?;
+
+        let board = match self.games.last() {
+            Some(last) => last.board,
+            None => return Ok(()),
+        };
+        let best = self
+            .games
+            .iter()
+            .filter(|game| game.won && game.board == board)
+            .map(|game| game.stats.elapsed)
+            .min();
+        if let Some(best) = best {
+            write!(
+                f,
+                " — best {:.1}s on {}",
+                best.as_secs_f64(),
+                board.difficulty()
+            )?;
+        }
+
+        Ok(())
+    }
+}
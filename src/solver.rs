@@ -0,0 +1,639 @@
+//! A logical solver, which finds the cells that are certainly safe or certainly mines.
+//!
+//! The solver only reads what the player can see: the numbers of the revealed cells, the flags and
+//! the total number of mines. It never looks at where the hidden mines are. Flags are trusted to
+//! be correct.
+//!
+//! The deductions are made in three stages, each one more expensive than the last:
+//!
+//! 1. Single-cell rules. A number that already touches as many flags as it shows has only safe
+//!    hidden neighbours. A number with exactly as many hidden neighbours as it has mines left
+//!    touches only mines.
+//! 2. Subset rules. If the hidden neighbours of one number are a subset of those of another, the
+//!    difference between the two holds the difference of their mines. This recognises patterns
+//!    like the well known 1-2-1 and 1-2-2-1.
+//! 3. Constraint satisfaction. The hidden cells next to numbers (the frontier) are split into
+//!    independent components, and every assignment of mines that satisfies all numbers of a
+//!    component is enumerated. A cell that is a mine in every assignment is certainly a mine, and
+//!    a cell that is a mine in none is certainly safe.
+//!
+//! After each stage, the new deductions are fed back in and the cheaper stages are run again.
+
+use crate::cell::CellState;
+use crate::field::Field;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+/// The maximum number of search steps spent on enumerating a single component. Components that
+/// need more are skipped by the constraint satisfaction stage.
+pub(crate) const ENUMERATION_BUDGET: usize = 1 << 20;
+
+/// The maximum number of cells of a component that is enumerated. Larger components are skipped
+/// like those that run out of the budget, before the tally of their assignments is allocated, which
+/// grows with the square of their size.
+const MAX_COMPONENT_CELLS: usize = 256;
+
+/// The number of search steps of the enumeration between two checks of the deadline.
+const DEADLINE_CHECK_STEPS: usize = 1 << 10;
+
+/// The cells the solver has proven to be safe or mines. Only hidden, unflagged cells are listed.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Deductions {
+    /// The positions of the cells that are certainly safe.
+    pub safe: Vec<(usize, usize)>,
+    /// The positions of the cells that are certainly mines.
+    pub mines: Vec<(usize, usize)>,
+}
+
+/// Finds the cells in the field that are certainly safe or certainly mines.
+pub fn solve(field: &Field) -> Deductions {
+    let mut board = Board::new(field);
+    board.deduce();
+    board.deductions()
+}
+
+/// Finds the cells in the field that are certainly safe or certainly mines, like `solve`, but
+/// gives up as soon as the deadline has passed. Returns `None` if it gave up.
+pub(crate) fn solve_until(field: &Field, deadline: Instant) -> Option<Deductions> {
+    let mut board = Board::new(field);
+    board.deadline = Some(deadline);
+    board.deduce().then(|| board.deductions())
+}
+
+/// What is known about a cell.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Knowledge {
+    /// The cell is hidden, and could be either safe or a mine.
+    Unknown,
+    /// The cell is hidden, but proven to be safe.
+    Safe,
+    /// The cell is hidden, but proven to be a mine.
+    Mine,
+    /// The cell has been revealed, so it is visible whether it is a mine.
+    Revealed,
+    /// The cell has been flagged, and is assumed to be a mine.
+    Flagged,
+}
+
+/// A number on the board: exactly `mines` of the `cells` are mines.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Constraint {
+    /// The indices of the unknown cells, in ascending order.
+    pub(crate) cells: Vec<usize>,
+    pub(crate) mines: usize,
+}
+
+/// The visible state of a field, together with everything the solver has deduced about it.
+pub(crate) struct Board {
+    pub(crate) width: usize,
+    height: usize,
+    states: Vec<CellState>,
+    pub(crate) knowledge: Vec<Knowledge>,
+    /// The total number of mines in the field.
+    total_mines: usize,
+    /// The moment to stop deducing, if there is one.
+    deadline: Option<Instant>,
+}
+
+impl Board {
+    pub(crate) fn new(field: &Field) -> Self {
+        let states: Vec<CellState> = field.cells().iter().map(|c| c.cell_state()).collect();
+        let knowledge = states
+            .iter()
+            .map(|state| match state {
+                CellState::Hidden => Knowledge::Unknown,
+                CellState::Flagged => Knowledge::Flagged,
+                CellState::RevealedMine | CellState::Neighbours(_) => Knowledge::Revealed,
+            })
+            .collect();
+
+        Self {
+            width: field.width(),
+            height: field.height(),
+            states,
+            knowledge,
+            total_mines: field.total_mines(),
+            deadline: None,
+        }
+    }
+
+    /// Returns the cells that have been proven to be safe or mines.
+    fn deductions(&self) -> Deductions {
+        let position = |index: usize| (index % self.width, index / self.width);
+        let mut deductions = Deductions::default();
+        for (index, knowledge) in self.knowledge.iter().enumerate() {
+            match knowledge {
+                Knowledge::Safe => deductions.safe.push(position(index)),
+                Knowledge::Mine => deductions.mines.push(position(index)),
+                Knowledge::Unknown | Knowledge::Revealed | Knowledge::Flagged => {}
+            }
+        }
+
+        deductions
+    }
+
+    /// Returns `true` if the deadline has passed.
+    fn is_late(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Returns the indices of the neighbours of the cell at the index, excluding the cell itself.
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> {
+        let width = self.width;
+        let (x, y) = (index % width, index / width);
+        let xs = x.saturating_sub(1)..=(x + 1).min(width - 1);
+
+        (y.saturating_sub(1)..=(y + 1).min(self.height - 1))
+            .flat_map(move |ny| xs.clone().map(move |nx| ny * width + nx))
+            .filter(move |&i| i != index)
+    }
+
+    /// Returns `true` if the cell is known to be a mine.
+    fn is_known_mine(&self, index: usize) -> bool {
+        match self.knowledge[index] {
+            Knowledge::Mine | Knowledge::Flagged => true,
+            Knowledge::Revealed => self.states[index] == CellState::RevealedMine,
+            Knowledge::Unknown | Knowledge::Safe => false,
+        }
+    }
+
+    /// Returns the number of mines that have not been found yet.
+    pub(crate) fn mines_left(&self) -> usize {
+        let known = (0..self.knowledge.len())
+            .filter(|&i| self.is_known_mine(i))
+            .count();
+        self.total_mines.saturating_sub(known)
+    }
+
+    /// Returns the indices of the cells of which nothing is known yet.
+    pub(crate) fn unknown(&self) -> Vec<usize> {
+        (0..self.knowledge.len())
+            .filter(|&i| self.knowledge[i] == Knowledge::Unknown)
+            .collect()
+    }
+
+    /// Returns a constraint for every revealed number that still touches unknown cells.
+    pub(crate) fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints = Vec::new();
+        for (index, state) in self.states.iter().enumerate() {
+            let n = match state {
+                CellState::Neighbours(n) if self.knowledge[index] == Knowledge::Revealed => *n,
+                _ => continue,
+            };
+
+            let mut cells = Vec::new();
+            let mut mines = 0;
+            for neighbour in self.neighbours(index) {
+                if self.knowledge[neighbour] == Knowledge::Unknown {
+                    cells.push(neighbour)
+                } else if self.is_known_mine(neighbour) {
+                    mines += 1
+                }
+            }
+
+            if !cells.is_empty() {
+                constraints.push(Constraint {
+                    cells,
+                    // Wrongly placed flags can make a number look overfull.
+                    mines: n.saturating_sub(mines),
+                });
+            }
+        }
+
+        constraints.sort_by(|a, b| a.cells.cmp(&b.cells));
+        constraints.dedup();
+        constraints
+    }
+
+    /// Marks the cells as safe or mines. Returns `true` if anything new was learned.
+    fn learn(&mut self, cells: &[usize], knowledge: Knowledge) -> bool {
+        let mut learned = false;
+        for &index in cells {
+            if self.knowledge[index] == Knowledge::Unknown {
+                self.knowledge[index] = knowledge;
+                learned = true;
+            }
+        }
+
+        learned
+    }
+
+    /// Runs the stages of deduction until nothing new can be learned. Returns `false` if the
+    /// deadline passed before that.
+    pub(crate) fn deduce(&mut self) -> bool {
+        loop {
+            if self.is_late() {
+                return false;
+            }
+            if self.apply_single_cell_rules() || self.apply_subset_rules() {
+                continue;
+            }
+            if !self.apply_enumeration() {
+                break;
+            }
+        }
+
+        // The enumeration gives up on components once the deadline has passed.
+        !self.is_late()
+    }
+
+    /// Applies the single-cell rules, and the same rules to the field as a whole using the total
+    /// number of mines.
+    fn apply_single_cell_rules(&mut self) -> bool {
+        let mut learned = false;
+        for constraint in self.constraints() {
+            if constraint.mines == 0 {
+                learned |= self.learn(&constraint.cells, Knowledge::Safe);
+            } else if constraint.mines == constraint.cells.len() {
+                learned |= self.learn(&constraint.cells, Knowledge::Mine);
+            }
+        }
+
+        let unknown = self.unknown();
+        let mines_left = self.mines_left();
+        if mines_left == 0 {
+            learned |= self.learn(&unknown, Knowledge::Safe);
+        } else if mines_left == unknown.len() {
+            learned |= self.learn(&unknown, Knowledge::Mine);
+        }
+
+        learned
+    }
+
+    /// Applies the subset rules to every pair of overlapping constraints.
+    fn apply_subset_rules(&mut self) -> bool {
+        let constraints = self.constraints();
+        // For every cell, the constraints it is part of. A constraint that holds all cells of
+        // another one holds its first cell, so only those constraints are compared.
+        let mut by_cell: HashMap<usize, Vec<&Constraint>> = HashMap::new();
+        for constraint in &constraints {
+            for &cell in &constraint.cells {
+                by_cell.entry(cell).or_default().push(constraint);
+            }
+        }
+
+        let mut learned = false;
+        for a in &constraints {
+            for b in &by_cell[&a.cells[0]] {
+                if a.cells.len() >= b.cells.len() || a.mines > b.mines {
+                    continue;
+                }
+                if !a
+                    .cells
+                    .iter()
+                    .all(|cell| b.cells.binary_search(cell).is_ok())
+                {
+                    continue;
+                }
+
+                let difference: Vec<usize> = b
+                    .cells
+                    .iter()
+                    .copied()
+                    .filter(|cell| a.cells.binary_search(cell).is_err())
+                    .collect();
+                let mines = b.mines - a.mines;
+                if mines == 0 {
+                    learned |= self.learn(&difference, Knowledge::Safe);
+                } else if mines == difference.len() {
+                    learned |= self.learn(&difference, Knowledge::Mine);
+                }
+            }
+        }
+
+        learned
+    }
+
+    /// Enumerates the assignments of every component of the frontier, and learns the cells that
+    /// have the same value in all of them.
+    fn apply_enumeration(&mut self) -> bool {
+        let mines_left = self.mines_left();
+        let mut learned = false;
+        for component in components(self.constraints()) {
+            let tally = match component.enumerate(mines_left, ENUMERATION_BUDGET, self.deadline) {
+                Some(tally) => tally,
+                None if self.is_late() => break,
+                None => continue,
+            };
+
+            let solutions = tally.solutions();
+            if solutions == 0 {
+                // The visible state is contradictory, for example because of a wrong flag.
+                continue;
+            }
+
+            for (i, &cell) in component.cells.iter().enumerate() {
+                let mine_count = tally.mine_count(i);
+                if mine_count == 0 {
+                    learned |= self.learn(&[cell], Knowledge::Safe);
+                } else if mine_count == solutions {
+                    learned |= self.learn(&[cell], Knowledge::Mine);
+                }
+            }
+        }
+
+        learned
+    }
+}
+
+/// A set of constraints that share no cells with the constraints of other components.
+pub(crate) struct Component {
+    /// The indices of the cells on the board, in ascending order.
+    pub(crate) cells: Vec<usize>,
+    /// The constraints, with their cells given as positions in `cells`.
+    constraints: Vec<Constraint>,
+}
+
+/// The result of enumerating a component, grouped by the number of mines in the assignment.
+pub(crate) struct Tally {
+    /// For every number of mines `k`, the number of assignments with `k` mines.
+    pub(crate) solutions: Vec<u64>,
+    /// For every number of mines `k` and cell `i` of the component, the number of assignments
+    /// with `k` mines in which the cell is a mine.
+    pub(crate) mines: Vec<Vec<u64>>,
+}
+
+impl Tally {
+    /// Returns the total number of assignments.
+    fn solutions(&self) -> u64 {
+        self.solutions.iter().sum()
+    }
+
+    /// Returns the number of assignments in which the `i`th cell of the component is a mine.
+    fn mine_count(&self, i: usize) -> u64 {
+        self.mines.iter().map(|counts| counts[i]).sum()
+    }
+}
+
+/// Splits the constraints into components of constraints that (indirectly) share cells.
+pub(crate) fn components(constraints: Vec<Constraint>) -> Vec<Component> {
+    // Union-find over the constraints, joining those that share a cell.
+    let mut parent: Vec<usize> = (0..constraints.len()).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+
+    let mut owner: HashMap<usize, usize> = HashMap::new();
+    for (c, constraint) in constraints.iter().enumerate() {
+        for &cell in &constraint.cells {
+            match owner.get(&cell) {
+                Some(&other) => {
+                    let (a, b) = (find(&mut parent, c), find(&mut parent, other));
+                    parent[a] = b;
+                }
+                None => {
+                    owner.insert(cell, c);
+                }
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<Constraint>> = BTreeMap::new();
+    for (c, constraint) in constraints.into_iter().enumerate() {
+        let root = find(&mut parent, c);
+        groups.entry(root).or_default().push(constraint);
+    }
+
+    groups
+        .into_values()
+        .map(|constraints| {
+            let mut cells: Vec<usize> = constraints
+                .iter()
+                .flat_map(|c| c.cells.iter().copied())
+                .collect();
+            cells.sort_unstable();
+            cells.dedup();
+
+            let constraints = constraints
+                .into_iter()
+                .map(|c| Constraint {
+                    cells: c
+                        .cells
+                        .iter()
+                        .map(|cell| cells.binary_search(cell).unwrap())
+                        .collect(),
+                    mines: c.mines,
+                })
+                .collect();
+
+            Component { cells, constraints }
+        })
+        .collect()
+}
+
+impl Component {
+    /// Enumerates every assignment of mines to the cells that satisfies all constraints and
+    /// contains at most `max_mines` mines.
+    ///
+    /// Returns `None` if the component has more than `MAX_COMPONENT_CELLS` cells, or if the
+    /// enumeration takes more than `budget` search steps, or runs past the deadline.
+    pub(crate) fn enumerate(
+        &self,
+        max_mines: usize,
+        budget: usize,
+        deadline: Option<Instant>,
+    ) -> Option<Tally> {
+        let n = self.cells.len();
+        // Every cell takes a search step before the first assignment is found.
+        if n > MAX_COMPONENT_CELLS || n >= budget {
+            return None;
+        }
+
+        let mut search = Search {
+            component: self,
+            by_cell: vec![Vec::new(); n],
+            assigned: vec![None; n],
+            placed: vec![0; self.constraints.len()],
+            open: self.constraints.iter().map(|c| c.cells.len()).collect(),
+            max_mines,
+            mines: 0,
+            budget,
+            deadline,
+            tally: Tally {
+                solutions: vec![0; n.min(max_mines) + 1],
+                mines: vec![vec![0; n]; n.min(max_mines) + 1],
+            },
+        };
+        for (c, constraint) in self.constraints.iter().enumerate() {
+            for &cell in &constraint.cells {
+                search.by_cell[cell].push(c);
+            }
+        }
+
+        if search.run(0) {
+            Some(search.tally)
+        } else {
+            None
+        }
+    }
+}
+
+/// The state of the backtracking search over the assignments of a component.
+struct Search<'c> {
+    component: &'c Component,
+    /// For every cell, the constraints it is part of.
+    by_cell: Vec<Vec<usize>>,
+    /// The value assigned to every cell so far: `Some(true)` for a mine.
+    assigned: Vec<Option<bool>>,
+    /// For every constraint, the number of mines assigned to its cells so far.
+    placed: Vec<usize>,
+    /// For every constraint, the number of its cells that have not been assigned yet.
+    open: Vec<usize>,
+    max_mines: usize,
+    mines: usize,
+    budget: usize,
+    deadline: Option<Instant>,
+    tally: Tally,
+}
+
+impl Search<'_> {
+    /// Assigns the cells from `cell` onwards. Returns `false` if the budget ran out or the
+    /// deadline passed.
+    fn run(&mut self, cell: usize) -> bool {
+        if self.budget == 0 {
+            return false;
+        }
+        self.budget -= 1;
+        // Reading the clock is slow compared to a search step, so it is only read now and then.
+        if self.budget.is_multiple_of(DEADLINE_CHECK_STEPS)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return false;
+        }
+
+        if cell == self.assigned.len() {
+            let k = self.mines;
+            self.tally.solutions[k] += 1;
+            for (i, value) in self.assigned.iter().enumerate() {
+                if *value == Some(true) {
+                    self.tally.mines[k][i] += 1;
+                }
+            }
+            return true;
+        }
+
+        for mine in [false, true] {
+            if mine && self.mines == self.max_mines {
+                continue;
+            }
+            if !self.fits(cell, mine) {
+                continue;
+            }
+
+            self.assign(cell, mine);
+            let within_budget = self.run(cell + 1);
+            self.unassign(cell, mine);
+            if !within_budget {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Returns `true` if assigning the value to the cell keeps all of its constraints satisfiable.
+    fn fits(&self, cell: usize, mine: bool) -> bool {
+        self.by_cell[cell].iter().all(|&c| {
+            let needed = self.component.constraints[c].mines;
+            let placed = self.placed[c] + mine as usize;
+            let open = self.open[c] - 1;
+            placed <= needed && placed + open >= needed
+        })
+    }
+
+    fn assign(&mut self, cell: usize, mine: bool) {
+        self.assigned[cell] = Some(mine);
+        self.mines += mine as usize;
+        for &c in &self.by_cell[cell] {
+            self.placed[c] += mine as usize;
+            self.open[c] -= 1;
+        }
+    }
+
+    fn unassign(&mut self, cell: usize, mine: bool) {
+        self.assigned[cell] = None;
+        self.mines -= mine as usize;
+        for &c in &self.by_cell[cell] {
+            self.placed[c] -= mine as usize;
+            self.open[c] += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::fixtures::field;
+
+    #[test]
+    fn number_with_as_many_hidden_neighbours_as_mines() {
+        // The opening at the right reveals the 1, which has a single hidden neighbour.
+        let f = field(&["*.."], &[(2, 0)]);
+        assert_eq!(
+            solve(&f),
+            Deductions {
+                safe: vec![],
+                mines: vec![(0, 0)],
+            }
+        );
+    }
+
+    #[test]
+    fn flags_are_trusted_and_not_listed() {
+        let mut f = field(&["*.", ".."], &[(1, 1)]);
+        f.flag(0, 0);
+        assert_eq!(
+            solve(&f),
+            Deductions {
+                safe: vec![(1, 0), (0, 1)],
+                mines: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn one_two_one() {
+        let f = field(&["*.*", "..."], &[(0, 1), (1, 1), (2, 1)]);
+        assert_eq!(
+            solve(&f),
+            Deductions {
+                safe: vec![(1, 0)],
+                mines: vec![(0, 0), (2, 0)],
+            }
+        );
+    }
+
+    #[test]
+    fn guess_leaves_nothing_to_deduce() {
+        let f = field(&["*.", ".."], &[(1, 1)]);
+        assert_eq!(solve(&f), Deductions::default());
+    }
+
+    #[test]
+    fn components_with_too_many_cells_are_not_enumerated() {
+        // A single constraint has as many assignments as cells, which fit in the budget.
+        let constraint = |cells: usize| Constraint {
+            cells: (0..cells).collect(),
+            mines: 1,
+        };
+        let enumerate =
+            |cells| components(vec![constraint(cells)])[0].enumerate(1, ENUMERATION_BUDGET, None);
+
+        assert!(enumerate(MAX_COMPONENT_CELLS).is_some());
+        assert!(enumerate(MAX_COMPONENT_CELLS + 1).is_none());
+    }
+
+    #[test]
+    fn solve_until_gives_up_after_the_deadline() {
+        let f = field(&["*.*", "..."], &[(0, 1), (1, 1), (2, 1)]);
+        assert_eq!(solve_until(&f, Instant::now()), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Statistics of a single game: its time, clicks, 3BV and efficiency.
+//!
+//! The 3BV (Bechtel's Board Benchmark Value) of a field is the smallest number of left clicks
+//! needed to clear it without flags or chords. Every opening, an area of cells without
+//! neighbouring mines, takes one click together with the numbers around it. Every number that does
+//! not border an opening takes a click of its own.
+
+use crate::field::Field;
+use std::fmt;
+use std::time::Duration;
+
+/// The clicks made in a game, counted by kind.
+///
+/// Every flag, reveal and reveal around applied to a field is counted, including those that do
+/// not change anything. Undoing a click does not take it back.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Clicks {
+    /// The reveals.
+    pub left: usize,
+    /// The flags placed and removed.
+    pub right: usize,
+    /// The reveals around a cell.
+    pub chord: usize,
+}
+
+impl Clicks {
+    /// Returns the number of clicks of all kinds.
+    pub fn total(&self) -> usize {
+        self.left + self.right + self.chord
+    }
+}
+
+/// The statistics of a game, as it has been played so far.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Stats {
+    /// The time spent playing, from the first reveal until the game is over or won.
+    pub elapsed: Duration,
+    /// The clicks made.
+    pub clicks: Clicks,
+    /// The 3BV of the field, or 0 if the mines have not been placed yet.
+    pub three_bv: usize,
+    /// The part of the 3BV that has been cleared.
+    pub solved_three_bv: usize,
+}
+
+impl Stats {
+    /// Returns the statistics of the game played on the field.
+    pub fn of(field: &Field) -> Self {
+        let (three_bv, solved_three_bv) = three_bv(field);
+
+        Self {
+            elapsed: field.elapsed(),
+            clicks: field.clicks(),
+            three_bv,
+            solved_three_bv,
+        }
+    }
+
+    /// Returns the cleared 3BV per second, or 0 before any time has passed.
+    pub fn three_bv_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.solved_three_bv as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the cleared 3BV per click, as a fraction. An efficiency above 1 means that flags
+    /// and chords saved clicks. Returns 0 before the first click.
+    pub fn efficiency(&self) -> f64 {
+        match self.clicks.total() {
+            0 => 0.0,
+            clicks => self.solved_three_bv as f64 / clicks as f64,
+        }
+    }
+}
+
+/// Writes the statistics on a single line, like
+/// `42.1s — 3BV 31/31 — 0.74 3BV/s — 30 clicks (25+4+1) — 103% efficiency`.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.1}s — 3BV {}/{} — {:.2} 3BV/s — {} clicks ({}+{}+{}) — {:.0}% efficiency",
+            self.elapsed.as_secs_f64(),
+            self.solved_three_bv,
+            self.three_bv,
+            self.three_bv_per_second(),
+            self.clicks.total(),
+            self.clicks.left,
+            self.clicks.right,
+            self.clicks.chord,
+            self.efficiency() * 100.0
+        )
+    }
+}
+
+/// Returns the 3BV of the field, and the part of it that has been cleared. Both are 0 if the
+/// mines have not been placed yet.
+///
+/// An opening counts as cleared once any of its cells is revealed, since revealing one reveals
+/// them all. A number that does not border an opening counts once it is revealed.
+pub fn three_bv(field: &Field) -> (usize, usize) {
+    let regions = field.three_bv_regions();
+    (regions.total, regions.solved(field))
+}
+
+/// The region of a cell that is not part of an opening, see `Regions`.
+const NOT_COUNTED: usize = usize::MAX;
+const NUMBER: usize = usize::MAX - 1;
+
+/// The parts of a mine layout that its 3BV is made of. These only depend on where the mines are,
+/// so they are found once, when the mines are placed, and the flood fill is not repeated for every
+/// update of the statistics.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub(crate) struct Regions {
+    /// For every cell, the index of the opening it is part of, `NUMBER` for a number that does not
+    /// border an opening, or `NOT_COUNTED` for mines and the numbers around openings.
+    cells: Vec<usize>,
+    openings: usize,
+    total: usize,
+}
+
+impl Regions {
+    /// Finds the openings and the numbers that do not border one in the field, of which the mines
+    /// have been placed and the neighbours counted.
+    pub(crate) fn of(field: &Field) -> Self {
+        let cells = field.cells();
+        let is_opening = |index: usize| !cells[index].is_mine() && cells[index].neighbours() == 0;
+
+        let mut regions = vec![NUMBER; cells.len()];
+        let mut openings = 0;
+        for start in 0..cells.len() {
+            if regions[start] != NUMBER || !is_opening(start) {
+                continue;
+            }
+
+            regions[start] = openings;
+            let mut stack = vec![start];
+            while let Some(index) = stack.pop() {
+                for neighbour in field.neighbourhood(index) {
+                    if regions[neighbour] != NUMBER {
+                        continue;
+                    }
+                    // The numbers around an opening are cleared along with it.
+                    if is_opening(neighbour) {
+                        regions[neighbour] = openings;
+                        stack.push(neighbour);
+                    } else {
+                        regions[neighbour] = NOT_COUNTED;
+                    }
+                }
+            }
+            openings += 1;
+        }
+
+        for (region, cell) in regions.iter_mut().zip(cells) {
+            if cell.is_mine() {
+                *region = NOT_COUNTED;
+            }
+        }
+        let numbers = regions.iter().filter(|&&region| region == NUMBER).count();
+
+        Self {
+            cells: regions,
+            openings,
+            total: openings + numbers,
+        }
+    }
+
+    /// Returns the part of the 3BV that has been cleared in the field.
+    fn solved(&self, field: &Field) -> usize {
+        let mut cleared = vec![false; self.openings];
+        let mut solved = 0;
+        for (&region, cell) in self.cells.iter().zip(field.cells()) {
+            if !cell.is_revealed() {
+                continue;
+            }
+            match region {
+                NOT_COUNTED => {}
+                NUMBER => solved += 1,
+                opening if !cleared[opening] => {
+                    cleared[opening] = true;
+                    solved += 1;
+                }
+                _ => {}
+            }
+        }
+        solved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::fixtures::field;
+    use crate::field::SafeStart;
+
+    #[test]
+    fn one_opening_with_the_numbers_around_it() {
+        let mut f = field(&["...", "...", "..*"], &[]);
+        assert_eq!(three_bv(&f), (1, 0));
+        // Revealing a number around the opening does not clear it.
+        f.reveal(1, 1);
+        assert_eq!(three_bv(&f), (1, 0));
+        f.reveal(0, 0);
+        assert_eq!(three_bv(&f), (1, 1));
+    }
+
+    #[test]
+    fn numbers_away_from_openings_count_on_their_own() {
+        // The 0 on the left is an opening with the 1 next to it, the 2 between the mines is not.
+        let mut f = field(&["..*.*"], &[]);
+        assert_eq!(three_bv(&f), (2, 0));
+        f.reveal(3, 0);
+        assert_eq!(three_bv(&f), (2, 1));
+        f.reveal(0, 0);
+        assert_eq!(three_bv(&f), (2, 2));
+    }
+
+    #[test]
+    fn no_openings() {
+        let mut f = field(&["*.", ".*"], &[]);
+        assert_eq!(three_bv(&f), (2, 0));
+        f.reveal(1, 0);
+        assert_eq!(three_bv(&f), (2, 1));
+    }
+
+    #[test]
+    fn separate_openings() {
+        // Two openings on either side of a wall of mines.
+        let f = field(&["..*..", "..*..", "..*.."], &[]);
+        assert_eq!(three_bv(&f), (2, 0));
+    }
+
+    #[test]
+    fn nothing_before_the_mines_are_placed() {
+        let f = Field::with_seed(9, 9, 10, SafeStart::Opening, 0).unwrap();
+        assert_eq!(three_bv(&f), (0, 0));
+    }
+}
@@ -0,0 +1,102 @@
+//! A presentation-neutral view of a field, which frontends draw in their own way.
+//!
+//! For every cell, the view tells what the player can see of it as a `CellState`, and how it is
+//! marked: by the cursor, by a hint, or by a heatmap. Choosing colors, characters or sprites for
+//! these is left to the frontend.
+
+use crate::cell::CellState;
+use crate::field::Field;
+use std::fmt;
+
+/// Everything a frontend needs to know to draw a single cell.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CellView {
+    /// The position of the cell in the field.
+    pub position: (usize, usize),
+    /// What the player can see of the cell.
+    pub state: CellState,
+    /// What is under a flag that has been revealed, which happens when the whole field is revealed
+    /// at the end of a game. `None` if the cell is not flagged, or the flag has not been revealed.
+    pub under_flag: Option<CellState>,
+    /// Whether the cursor is on the cell.
+    pub cursor: bool,
+    /// Whether the cell is highlighted by a hint, as being certainly a mine.
+    pub highlight: bool,
+    /// The probability that the cell is a mine, if a heatmap is shown and the cell is hidden.
+    pub heat: Option<f64>,
+}
+
+impl Field {
+    /// Returns the views of all cells of the field, row by row.
+    ///
+    /// If `probabilities` is given, as computed by `probability::mine_probabilities`, the hidden
+    /// cells carry their probability of being a mine for a heatmap.
+    pub fn view<'f>(
+        &'f self,
+        probabilities: Option<&'f [f64]>,
+    ) -> impl Iterator<Item = CellView> + 'f {
+        (0..self.height())
+            .flat_map(move |y| (0..self.width()).map(move |x| self.cell_view(x, y, probabilities)))
+    }
+
+    /// Returns the view of the cell at the position, see `Field::view`.
+    pub fn cell_view(&self, x: usize, y: usize, probabilities: Option<&[f64]>) -> CellView {
+        let index = y * self.width() + x;
+        let cell = &self.cells()[index];
+        let position = (x, y);
+        let state = cell.cell_state();
+
+        let under_flag = if cell.is_flagged() && cell.is_revealed() {
+            if cell.is_mine() {
+                Some(CellState::RevealedMine)
+            } else {
+                Some(CellState::Neighbours(cell.neighbours()))
+            }
+        } else {
+            None
+        };
+        let heat = match (state, probabilities) {
+            (CellState::Hidden, Some(probabilities)) => Some(probabilities[index]),
+            _ => None,
+        };
+
+        CellView {
+            position,
+            state,
+            under_flag,
+            cursor: position == (self.cursor_pos_x(), self.cursor_pos_y()),
+            highlight: self.highlight() == Some(position),
+            heat,
+        }
+    }
+}
+
+/// Writes the cell state as a single plain character: `F` for a flag, `M` for a revealed mine,
+/// the number of neighbouring mines for other revealed cells, and `.` for hidden cells.
+impl fmt::Display for CellState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellState::Flagged => write!(f, "F"),
+            CellState::RevealedMine => write!(f, "M"),
+            CellState::Neighbours(n) => write!(f, "{}", n),
+            CellState::Hidden => write!(f, "."),
+        }
+    }
+}
+
+/// Writes the field as plain text, one row per line, without any styling.
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows: Vec<String> = self
+            .cells()
+            .chunks(self.width())
+            .map(|row| {
+                row.iter()
+                    .map(|cell| format!(" {}", cell.cell_state()))
+                    .collect()
+            })
+            .collect();
+
+        write!(f, "{}", rows.join("\n"))
+    }
+}